@@ -3,6 +3,9 @@
 use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
 
+use crate::gameplay::PlayerId;
+use crate::model::game_mode::Event;
+
 /// A specialised [`Error`] used throughout the library.
 ///
 /// [`Error`]: StdError
@@ -11,6 +14,20 @@ pub enum Error {
     DispatchError(Option<String>),
     ResponseError(Option<String>),
     NoUserFound,
+    /// No game mode implementation is registered for the event.
+    UnregisteredGameMode(Event),
+    /// No Brawler implementation is registered for the Brawler name.
+    UnregisteredBrawler(String),
+    /// A replay diverged from the recorded game.
+    ReplayMismatch(String),
+    /// A player forfeited the game by missing too many turns.
+    AfkForfeit(PlayerId),
+    /// A player surrendered or was made to forfeit the game.
+    Forfeit(PlayerId),
+    /// The game was cancelled before it ended.
+    Cancelled,
+    /// A map layout couldn't be loaded.
+    InvalidMap(String),
     MiscError(String),
 }
 
@@ -34,6 +51,15 @@ impl Display for Error {
                 }
             },
             Self::NoUserFound => write!(f, "no user found"),
+            Self::UnregisteredGameMode(e) => write!(f, "no game mode registered for `{}`", e),
+            Self::UnregisteredBrawler(name) => write!(f, "no Brawler registered for `{}`", name),
+            Self::ReplayMismatch(e) => write!(f, "replay mismatch: {}", e),
+            Self::AfkForfeit(id) => {
+                write!(f, "player {} forfeited by missing too many turns", id.0)
+            },
+            Self::Forfeit(id) => write!(f, "player {} forfeited", id.0),
+            Self::Cancelled => write!(f, "game cancelled"),
+            Self::InvalidMap(e) => write!(f, "invalid map: {}", e),
             Self::MiscError(e) => write!(f, "{}", e),
         }
     }
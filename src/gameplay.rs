@@ -3,22 +3,54 @@
 //! This module defines the most important data types and their interactions
 //! in the form of a "brawl".
 
+pub mod ai;
+pub mod arena;
 pub mod battle_brawler;
 pub mod battle_game_mode;
 pub mod battle_log;
+pub mod event;
 pub mod player;
+pub mod replay;
+pub mod snapshot;
+pub mod status;
+#[cfg(test)]
+mod test_utils;
+pub mod timeout;
+pub mod trophies;
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::mem;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
 
+#[doc(inline)]
+pub use ai::AiHandler;
+#[doc(inline)]
+pub use arena::Arena;
 use async_trait::async_trait;
 #[doc(inline)]
-pub use battle_brawler::{BrawlerExt, BrawlerInfo, Spawn};
+pub use battle_brawler::{BrawlerExt, BrawlerInfo, BrawlerRegistry, Spawn};
+#[doc(inline)]
+pub use battle_game_mode::{BattleGameMode, GameModeExt, GameModeRegistry, Moves};
+use battle_log::BattleLogEntry;
+#[doc(inline)]
+pub use event::GameEvent;
+use player::Position;
 #[doc(inline)]
-pub use battle_game_mode::{BattleGameMode, Moves};
+pub use player::{Player, PlayerId, TeamId};
 #[doc(inline)]
-pub use player::{Player, PlayerId};
+pub use replay::Replay;
+use replay::{RecordedMove, Recorder};
+use serde::{Deserialize, Serialize};
+#[doc(inline)]
+pub use snapshot::Snapshot;
+use snapshot::{PlayerSnapshot, Resume, SnapshotHandle};
+use trophies::TrophyCalculator;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::utils::rng;
 
 /// Represents a brawl/game.
 #[derive(Clone)]
@@ -27,6 +59,27 @@ pub struct Game {
     pub players: Players,
     pub result: Option<GameResult>,
     pub handler: Arc<dyn GameHandler>,
+    /// The game mode implementations used to run the game.
+    ///
+    /// It contains the built-in game modes by default.
+    pub registry: GameModeRegistry,
+    /// The seed of the random number generator used to run the game.
+    ///
+    /// It is random by default. Games with the same seed, players and moves
+    /// have the same outcome.
+    pub seed: u64,
+    /// The calculator used for the trophy changes in the battle log.
+    pub trophies: TrophyCalculator,
+    /// The token used to stop the game from outside while it runs.
+    ///
+    /// Clone it before running the game to keep a handle to the game.
+    pub cancel: CancelToken,
+    /// The handle used to take snapshots of the game while it runs.
+    ///
+    /// Clone it before running the game to keep a handle to the game.
+    pub snapshot: SnapshotHandle,
+    /// The moves chosen again before the players are prompted, if the game is resumed.
+    resumed: Vec<RecordedMove>,
 }
 
 impl Game {
@@ -36,45 +89,560 @@ impl Game {
         players: Players,
         handler: H,
     ) -> Self {
-        Self { game_mode: gamemode, players, result: None, handler: Arc::new(handler) }
+        Self {
+            game_mode: gamemode,
+            players,
+            result: None,
+            handler: Arc::new(handler),
+            registry: GameModeRegistry::default(),
+            seed: rand::random(),
+            trophies: TrophyCalculator::default(),
+            cancel: CancelToken::new(),
+            snapshot: SnapshotHandle::new(),
+            resumed: Vec::new(),
+        }
+    }
+
+    /// Creates a [`Game`] which resumes the game of a [`Snapshot`].
+    ///
+    /// The players are restored with the Brawlers in `brawlers` and the game mode is run
+    /// with the implementation in `registry`, which must be the ones the game started
+    /// with. When the game runs, the moves of the snapshot are chosen again and the
+    /// messages dispatched till the next player is prompted are discarded. The players
+    /// are prompted through `handler` after that.
+    ///
+    /// Returns [`Error::UnregisteredBrawler`] if the Brawler of a player is not registered.
+    /// Running the game returns [`Error::ReplayMismatch`] if the moves of the snapshot
+    /// can't be chosen again.
+    pub fn resume<H: 'static + GameHandler>(
+        snapshot: Snapshot,
+        brawlers: &BrawlerRegistry,
+        registry: GameModeRegistry,
+        handler: H,
+    ) -> Result<Self> {
+        let players = snapshot.players(brawlers)?;
+
+        let mut game = Self::new(BattleGameMode::new(snapshot.event), players, handler);
+        game.registry = registry;
+        game.seed = snapshot.seed;
+        game.resumed = snapshot.moves;
+
+        Ok(game)
     }
 
     /// Runs the game.
+    ///
+    /// The game mode implementation is looked up from the game's [`registry`].
+    ///
+    /// Returns [`Error::Cancelled`] if the game is cancelled through its [`cancel`] token.
+    ///
+    /// [`registry`]: Self::registry
+    /// [`cancel`]: Self::cancel
     pub async fn run(mut self) -> Result<GameResult> {
-        self.game_mode.run(&mut self.players, self.handler.as_ref()).await
+        let handler = Arc::clone(&self.handler);
+
+        self.run_with(handler.as_ref()).await
+    }
+
+    /// Runs the game and records it in a [`Replay`].
+    ///
+    /// The replay can be played again with [`Replay::play`] to reproduce the game. See
+    /// [`Replay::play`] for the forfeits which may not be reproduced.
+    pub async fn run_recorded(mut self) -> Result<(GameResult, Replay)> {
+        let event = self.game_mode.event;
+        let roster = self.players.iter().map(PlayerSnapshot::new).collect();
+        let arena = self.players.arena().clone();
+        let handler = Arc::clone(&self.handler);
+
+        let result = self.run_with(handler.as_ref()).await?;
+        let moves = self.snapshot.get().map(|snapshot| snapshot.moves).unwrap_or_default();
+        let mut replay = Replay::new(event, self.seed, roster, moves, result.clone());
+        replay.arena = arena;
+
+        Ok((result, replay))
+    }
+
+    /// Runs the game and creates a [`BattleLogEntry`] for it.
+    ///
+    /// The trophy changes are calculated with the game's [`trophies`](Self::trophies).
+    pub async fn run_logged(mut self) -> Result<(GameResult, BattleLogEntry)> {
+        let event = self.game_mode.event;
+        let handler = Arc::clone(&self.handler);
+
+        let result = self.run_with(handler.as_ref()).await?;
+        let log = BattleLogEntry::from_result(event, &result, &self.players, &self.trophies);
+
+        Ok((result, log))
+    }
+
+    /// Runs the game with `handler`, taking snapshots and stopping it when the cancel
+    /// token is used.
+    async fn run_with(&mut self, handler: &dyn GameHandler) -> Result<GameResult> {
+        let event = self.game_mode.event;
+        let roster = self.players.iter().map(PlayerSnapshot::new).collect();
+        let mut snapshot = Snapshot::new(event, self.seed, roster, Vec::new());
+        snapshot.arena = self.players.arena().clone();
+        self.snapshot.start(snapshot);
+
+        let resume = Resume::new(mem::take(&mut self.resumed), handler);
+        let recorder = Recorder::new(&resume, &self.snapshot);
+        let handler = Cancellable { handler: &recorder, token: &self.cancel };
+        let mut rng = rng::seeded(self.seed);
+
+        let result = self.registry.run(event, &mut self.players, &handler, &mut rng).await?;
+
+        if resume.remaining() > 0 {
+            return Err(Error::ReplayMismatch(format!(
+                "game ended with {} moves of the snapshot left",
+                resume.remaining()
+            )));
+        }
+
+        Ok(result)
+    }
+}
+
+/// The reason a game is stopped through a [`CancelToken`].
+#[derive(Clone, Copy, Debug)]
+enum Stop {
+    /// The game is cancelled without a result.
+    Cancel,
+    /// The team of the player forfeits the game.
+    Forfeit(PlayerId),
+}
+
+/// A handle to stop a running [`Game`] from outside, for example, when a player
+/// leaves or the bot shuts down.
+///
+/// The game stops at once, even while it waits for an event to be delivered or for a
+/// player to choose a move.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<Mutex<CancelState>>);
+
+/// The shared state of a [`CancelToken`].
+#[derive(Debug, Default)]
+struct CancelState {
+    /// The reason the game is stopped, if it has been stopped.
+    stop: Option<Stop>,
+    /// The waker of the game waiting on a player, if any.
+    waker: Option<Waker>,
+}
+
+impl CancelToken {
+    /// Creates a new [`CancelToken`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the game, which ends with [`Error::Cancelled`] instead of a result.
+    pub fn cancel(&self) {
+        self.stop(Stop::Cancel);
+    }
+
+    /// Makes the team of the player with given ID forfeit the game.
+    ///
+    /// The game ends with a [`GameResult::Forfeit`].
+    pub fn forfeit(&self, player_id: PlayerId) {
+        self.stop(Stop::Forfeit(player_id));
+    }
+
+    /// Checks if the game has been cancelled or forfeited.
+    pub fn is_stopped(&self) -> bool {
+        self.0.lock().is_ok_and(|state| state.stop.is_some())
+    }
+
+    fn stop(&self, stop: Stop) {
+        let waker = match self.0.lock() {
+            Ok(mut state) => {
+                state.stop.get_or_insert(stop);
+                state.waker.take()
+            },
+            Err(_) => None,
+        };
+
+        // The game is woken up outside the lock, as it checks the token when polled.
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Returns the error which stops the game, if it has been stopped.
+    fn check(&self) -> Result<()> {
+        match self.0.lock().ok().and_then(|state| state.stop) {
+            Some(Stop::Cancel) => Err(Error::Cancelled),
+            Some(Stop::Forfeit(id)) => Err(Error::Forfeit(id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Waits for `future` unless the game is stopped first, in which case the future
+    /// is dropped and the error which stops the game is returned.
+    async fn until_stopped<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        self.check()?;
+
+        let mut future = pin!(future);
+        poll_fn(|cx| {
+            if let Poll::Ready(res) = future.as_mut().poll(cx) {
+                return Poll::Ready(res);
+            }
+
+            // The waker is stored before checking again so that a stop in between
+            // still wakes up the game.
+            if let Ok(mut state) = self.0.lock() {
+                state.waker = Some(cx.waker().clone());
+            }
+
+            match self.check() {
+                Ok(()) => Poll::Pending,
+                Err(e) => Poll::Ready(Err(e)),
+            }
+        })
+        .await
     }
 }
 
-/// Represents the players of a game.
+/// A [`GameHandler`] which stops the game once its [`CancelToken`] is used.
+struct Cancellable<'a> {
+    /// The handler which is wrapped.
+    handler: &'a dyn GameHandler,
+    /// The token which stops the game.
+    token: &'a CancelToken,
+}
+
+#[async_trait]
+impl GameHandler for Cancellable<'_> {
+    async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()> {
+        self.token.until_stopped(self.handler.info(player_id, msg)).await
+    }
+
+    async fn event(&self, player: &Player, event: &GameEvent) -> Result<()> {
+        // The result of a forfeited game is still delivered.
+        if matches!(event, GameEvent::GameOver(_)) {
+            return self.handler.event(player, event).await;
+        }
+
+        self.token.until_stopped(self.handler.event(player, event)).await
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        moves: Moves<'a>,
+        player: &Player,
+        players: &Players,
+    ) -> Result<usize> {
+        self.token.until_stopped(self.handler.get_move_idx(moves, player, players)).await
+    }
+}
+
+/// Represents the players of a game, grouped into teams.
+///
+/// The players take their turns in the order returned by [`turn_order`], which
+/// alternates between the teams.
+///
+/// The players move on the [`Arena`] of the game, which is the default arena unless
+/// it is set with [`set_arena`].
+///
+/// [`turn_order`]: Self::turn_order
+/// [`set_arena`]: Self::set_arena
 #[derive(Clone, Debug)]
-pub struct Players(pub Player, pub Player);
+pub struct Players {
+    /// The players, in the order they were added.
+    players: Vec<Player>,
+    /// The map the players move on.
+    arena: Arena,
+}
 
 impl Players {
-    /// Creates a new [`Players`](Players) instance.
+    /// Creates a new [`Players`](Players) instance for a 1v1 game.
+    ///
+    /// Both players are put in separate teams.
     pub fn new(first: Player, second: Player) -> Self {
-        Self(first, second)
+        Self::from_teams(vec![vec![first], vec![second]])
+    }
+
+    /// Creates a new [`Players`](Players) instance from teams of players.
+    ///
+    /// Each team is assigned a [`TeamId`] corresponding to its index and the players
+    /// are placed at the spawn point of their team.
+    pub fn from_teams(teams: Vec<Vec<Player>>) -> Self {
+        let mut players = Vec::new();
+        for (idx, team) in teams.into_iter().enumerate() {
+            for mut player in team {
+                player.team = TeamId(idx as u8);
+                players.push(player);
+            }
+        }
+
+        let mut players = Self { players, arena: Arena::default() };
+        players.place();
+
+        players
+    }
+
+    /// Creates a new [`Players`](Players) instance where every player is in their own team.
+    ///
+    /// This is used for modes like Solo Showdown.
+    pub fn solo(players: Vec<Player>) -> Self {
+        Self::from_teams(players.into_iter().map(|p| vec![p]).collect())
+    }
+
+    /// Returns the arena the players move on.
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    /// Sets the arena the players move on and places the players at the spawn
+    /// point of their team.
+    pub fn set_arena(&mut self, arena: Arena) -> &mut Self {
+        self.arena = arena;
+        self.place();
+        self
+    }
+
+    /// Returns the position where a player spawns, `None` if there is no player
+    /// with given ID.
+    pub fn spawn_point(&self, id: PlayerId) -> Option<Position> {
+        let team = self.get(id)?.team;
+        let slot = self.team(team).position(|p| p.id == id)?;
+
+        let spawn = self.team_spawn(team)?;
+        Some(self.arena.spawn_slot(spawn, slot, self.team(team).count()))
+    }
+
+    /// Returns the spawn point of a team, `None` if there is no player in the team.
+    pub fn team_spawn(&self, team: TeamId) -> Option<Position> {
+        let teams = self.teams();
+        let idx = teams.iter().position(|t| *t == team)?;
+
+        Some(self.arena.spawn(team, idx, teams.len()))
+    }
+
+    /// Checks if a player can see another player.
+    ///
+    /// Players always see their teammates. An enemy standing in a bush is hidden from
+    /// a player unless someone from the player's team is close to the enemy.
+    pub fn is_visible(&self, viewer: PlayerId, target: PlayerId) -> bool {
+        let (viewer, target) = match (self.get(viewer), self.get(target)) {
+            (Some(viewer), Some(target)) => (viewer, target),
+            _ => return false,
+        };
+
+        if viewer.is_teammate(target) {
+            return true;
+        }
+
+        let observers: Vec<Position> = self
+            .team(viewer.team)
+            .filter(|p| p.state.is_alive() && !p.state.is_respawning())
+            .map(|p| p.state.position)
+            .collect();
+
+        !self.arena.is_hidden(&target.state.position, &observers)
+    }
+
+    /// Returns the players as seen by a player, leaving out the enemies hidden from them.
+    pub fn view(&self, viewer: PlayerId) -> Players {
+        let players =
+            self.players.iter().filter(|p| self.is_visible(viewer, p.id)).cloned().collect();
+
+        Self { players, arena: self.arena.clone() }
+    }
+
+    /// Places every player at the position where they spawn.
+    fn place(&mut self) {
+        let ids: Vec<PlayerId> = self.players.iter().map(|p| p.id).collect();
+
+        for id in ids {
+            if let Some(position) = self.spawn_point(id) {
+                if let Some(player) = self.get_mut(id) {
+                    player.state.position = position;
+                }
+            }
+        }
+    }
+
+    /// Returns the number of players.
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Checks if there are no players.
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Returns an iterator over all the players.
+    pub fn iter(&self) -> impl Iterator<Item = &Player> {
+        self.players.iter()
+    }
+
+    /// Returns an iterator that allows modifying each player.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Player> {
+        self.players.iter_mut()
+    }
+
+    /// Returns a reference to the player with given ID.
+    pub fn get(&self, id: PlayerId) -> Option<&Player> {
+        self.players.iter().find(|p| p.id == id)
+    }
+
+    /// Returns a mutable reference to the player with given ID.
+    pub fn get_mut(&mut self, id: PlayerId) -> Option<&mut Player> {
+        self.players.iter_mut().find(|p| p.id == id)
+    }
+
+    /// Returns mutable references to two different players.
+    ///
+    /// Returns `None` if either of the players is not present or both the IDs are same.
+    pub fn pair_mut(
+        &mut self,
+        first: PlayerId,
+        second: PlayerId,
+    ) -> Option<(&mut Player, &mut Player)> {
+        let first_idx = self.players.iter().position(|p| p.id == first)?;
+        let second_idx = self.players.iter().position(|p| p.id == second)?;
+
+        if first_idx < second_idx {
+            let (left, right) = self.players.split_at_mut(second_idx);
+            Some((&mut left[first_idx], &mut right[0]))
+        } else if first_idx > second_idx {
+            let (left, right) = self.players.split_at_mut(first_idx);
+            Some((&mut right[0], &mut left[second_idx]))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the IDs of all the teams in the order of their first player.
+    pub fn teams(&self) -> Vec<TeamId> {
+        let mut teams = Vec::new();
+        for player in &self.players {
+            if !teams.contains(&player.team) {
+                teams.push(player.team);
+            }
+        }
+
+        teams
+    }
+
+    /// Returns an iterator over the players of a team.
+    pub fn team(&self, team: TeamId) -> impl Iterator<Item = &Player> {
+        self.players.iter().filter(move |p| p.team == team)
+    }
+
+    /// Returns an iterator over the enemies of a player.
+    ///
+    /// An enemy is any player that is not in the same team as the player.
+    pub fn enemies(&self, id: PlayerId) -> impl Iterator<Item = &Player> {
+        let team = self.get(id).map(|p| p.team);
+        self.players.iter().filter(move |p| Some(p.team) != team)
+    }
+
+    /// Returns an iterator over the teammates of a player, excluding the player.
+    pub fn teammates(&self, id: PlayerId) -> impl Iterator<Item = &Player> {
+        let team = self.get(id).map(|p| p.team);
+        self.players.iter().filter(move |p| Some(p.team) == team && p.id != id)
+    }
+
+    /// Returns the order in which the players take their turns in a round.
+    ///
+    /// The order alternates between the teams, that is, the first player of every
+    /// team plays before the second player of any team.
+    pub fn turn_order(&self) -> Vec<PlayerId> {
+        let teams: Vec<Vec<PlayerId>> =
+            self.teams().into_iter().map(|t| self.team(t).map(|p| p.id).collect()).collect();
+        let largest = teams.iter().map(Vec::len).max().unwrap_or(0);
+
+        (0..largest).flat_map(|i| teams.iter().filter_map(move |t| t.get(i).copied())).collect()
     }
 }
 
 /// Represents the result of a game.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum GameResult {
-    /// Game ended with one player winning and one losing.
-    Decisive { winner: PlayerId, loser: PlayerId },
+    /// Game ended with one team winning and the other teams losing.
+    Decisive { winner: TeamId, losers: Vec<TeamId> },
+    /// Game ended with the teams ranked by their placements.
+    ///
+    /// The first team is the winner and the last team is placed last.
+    Ranked(Vec<TeamId>),
     /// Game ended in a draw.
     Draw,
+    /// Game ended early because a player of the `forfeited` team surrendered,
+    /// left or stopped responding.
+    ///
+    /// `placements` ranks all the teams, including the forfeited team, like a
+    /// [`Ranked`](Self::Ranked) result. The first team is the winner.
+    Forfeit { forfeited: TeamId, placements: Vec<TeamId> },
 }
 
 impl GameResult {
     /// Checks if the result is decisive.
     pub fn is_decisive(&self) -> bool {
-        matches!(self, Self::Decisive { winner: _, loser: _ })
+        matches!(self, Self::Decisive { winner: _, losers: _ })
+    }
+
+    /// Checks if the result is ranked.
+    pub fn is_ranked(&self) -> bool {
+        matches!(self, Self::Ranked(_))
     }
 
     /// Checks if the result is a draw.
     pub fn is_draw(&self) -> bool {
         matches!(self, Self::Draw)
     }
+
+    /// Checks if the game ended because of a forfeit.
+    pub fn is_forfeit(&self) -> bool {
+        matches!(self, Self::Forfeit { forfeited: _, placements: _ })
+    }
+
+    /// Returns the winning team, if any.
+    pub fn winner(&self) -> Option<TeamId> {
+        match self {
+            Self::Decisive { winner, losers: _ } => Some(*winner),
+            Self::Ranked(placements) => placements.first().copied(),
+            Self::Draw => None,
+            Self::Forfeit { forfeited, placements } => {
+                placements.first().copied().filter(|t| t != forfeited)
+            },
+        }
+    }
+
+    /// Returns the number of placements in the result.
+    ///
+    /// A decisive result has two placements, the winner and the losers. A forfeit has
+    /// a placement for every team.
+    pub fn placements_len(&self) -> usize {
+        match self {
+            Self::Decisive { winner: _, losers } => usize::from(!losers.is_empty()) + 1,
+            Self::Ranked(placements) => placements.len(),
+            Self::Draw => 0,
+            Self::Forfeit { forfeited: _, placements } => placements.len(),
+        }
+    }
+
+    /// Returns the placement of a team, starting from 1 for the winner.
+    ///
+    /// All losing teams of a decisive result are placed second. Returns `None` for a
+    /// draw or if the team is not present in the result.
+    pub fn placement(&self, team: TeamId) -> Option<usize> {
+        match self {
+            Self::Decisive { winner, losers } => {
+                if *winner == team {
+                    Some(1)
+                } else if losers.contains(&team) {
+                    Some(2)
+                } else {
+                    None
+                }
+            },
+            Self::Ranked(placements) | Self::Forfeit { forfeited: _, placements } => {
+                placements.iter().position(|t| *t == team).map(|i| i + 1)
+            },
+            Self::Draw => None,
+        }
+    }
 }
 
 /// A trait to enable communication between the game and the players during a brawl.
@@ -85,14 +653,294 @@ impl GameResult {
 ///
 /// The game is terminated if any errors originate while informing or prompting players.
 #[async_trait]
-pub trait GameHandler {
+pub trait GameHandler: Send + Sync {
     /// This is used to share information about the game to the players.
     async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()>;
 
+    /// This is used to notify a player about an event in the game.
+    ///
+    /// By default, the event is described with [`GameEvent::message`] and shared
+    /// using [`info`](Self::info). Override it to render or localize the events.
+    async fn event(&self, player: &Player, event: &GameEvent) -> Result<()> {
+        self.info(&player.id, &event.message(player)).await
+    }
+
+    /// This is used to prompt a player to choose one of the possible moves.
+    ///
+    /// `player` is the player whose turn it is. Moves which act on another player,
+    /// such as attacks, carry the ID of their target, which can be looked up in `players`.
+    ///
+    /// The index of the chosen move must be returned.
     async fn get_move_idx<'a>(
         &self,
         moves: Moves<'a>,
-        first: &Player,
-        second: &Player,
+        player: &Player,
+        players: &Players,
     ) -> Result<usize>;
 }
+
+/// A [`GameHandler`] which routes the events and prompts of every player to the
+/// handler bound to them.
+///
+/// This allows each player of a game to be driven by a different source, for example,
+/// a human through chat, an [`AiHandler`] or a remote client. Players without a bound
+/// handler use the fallback handler, if any.
+#[derive(Clone, Default)]
+pub struct PlayerHandlers {
+    /// The handlers bound to the players.
+    handlers: HashMap<PlayerId, Arc<dyn GameHandler>>,
+    /// The handler used for players without a bound handler.
+    fallback: Option<Arc<dyn GameHandler>>,
+}
+
+impl PlayerHandlers {
+    /// Creates a new [`PlayerHandlers`] with no bound handlers and no fallback.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `handler` to the player with given ID.
+    ///
+    /// Use [`bind_shared`](Self::bind_shared) to bind the same handler to many players.
+    pub fn bind<H: 'static + GameHandler>(&mut self, player_id: PlayerId, handler: H) -> &mut Self {
+        self.bind_shared(player_id, Arc::new(handler))
+    }
+
+    /// Binds a shared `handler` to the player with given ID.
+    pub fn bind_shared(&mut self, player_id: PlayerId, handler: Arc<dyn GameHandler>) -> &mut Self {
+        self.handlers.insert(player_id, handler);
+        self
+    }
+
+    /// Sets the handler used for players without a bound handler.
+    pub fn set_fallback<H: 'static + GameHandler>(&mut self, handler: H) -> &mut Self {
+        self.fallback = Some(Arc::new(handler));
+        self
+    }
+
+    /// Returns the handler of the player with given ID.
+    ///
+    /// Returns [`Error::NoUserFound`] if no handler is bound to the player and there
+    /// is no fallback handler.
+    pub fn get(&self, player_id: &PlayerId) -> Result<&dyn GameHandler> {
+        self.handlers
+            .get(player_id)
+            .or(self.fallback.as_ref())
+            .map(AsRef::as_ref)
+            .ok_or(Error::NoUserFound)
+    }
+}
+
+#[async_trait]
+impl GameHandler for PlayerHandlers {
+    async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()> {
+        self.get(player_id)?.info(player_id, msg).await
+    }
+
+    async fn event(&self, player: &Player, event: &GameEvent) -> Result<()> {
+        self.get(&player.id)?.event(player, event).await
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        moves: Moves<'a>,
+        player: &Player,
+        players: &Players,
+    ) -> Result<usize> {
+        self.get(&player.id)?.get_move_idx(moves, player, players).await
+    }
+}
+
+#[cfg(test)]
+mod test_gameplay {
+    use std::future::pending;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::{Context, Wake};
+
+    use super::*;
+    use crate::gameplay::ai::RandomStrategy;
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::model::game_mode::Event;
+
+    /// A handler which logs the players it is called for and always chooses the
+    /// first move.
+    struct LogHandler(&'static str, Arc<Mutex<Vec<(&'static str, PlayerId)>>>);
+
+    #[async_trait]
+    impl GameHandler for LogHandler {
+        async fn info(&self, player_id: &PlayerId, _msg: &str) -> Result<()> {
+            self.1.lock().unwrap().push((self.0, *player_id));
+            Ok(())
+        }
+
+        async fn get_move_idx<'a>(
+            &self,
+            _moves: Moves<'a>,
+            player: &Player,
+            _players: &Players,
+        ) -> Result<usize> {
+            self.1.lock().unwrap().push((self.0, player.id));
+            Ok(0)
+        }
+    }
+
+    /// A handler which never chooses a move.
+    struct AfkHandler;
+
+    #[async_trait]
+    impl GameHandler for AfkHandler {
+        async fn info(&self, _player_id: &PlayerId, _msg: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_move_idx<'a>(
+            &self,
+            _moves: Moves<'a>,
+            _player: &Player,
+            _players: &Players,
+        ) -> Result<usize> {
+            pending().await
+        }
+    }
+
+    /// A custom game mode won by the team which tags the most.
+    #[derive(Debug)]
+    struct Tag;
+
+    /// The state of a player in [`Tag`].
+    #[derive(Default, Deserialize, Serialize)]
+    struct TagState {
+        tags: u32,
+    }
+
+    #[async_trait]
+    impl GameModeExt for Tag {
+        async fn run(
+            &self,
+            players: &mut Players,
+            _handler: &dyn GameHandler,
+            _rng: &mut rng::GameRng,
+        ) -> Result<GameResult> {
+            for player in players.iter_mut() {
+                let tags = if player.team == TeamId(1) { 2 } else { 1 };
+                player.state.set_custom_mode(&TagState { tags })?;
+            }
+
+            let scores: Vec<(TeamId, u32)> = players
+                .teams()
+                .into_iter()
+                .map(|team| {
+                    let tags = players
+                        .team(team)
+                        .filter_map(|p| p.state.custom_mode::<TagState>())
+                        .map(|state| state.tags)
+                        .sum();
+                    (team, tags)
+                })
+                .collect();
+
+            Ok(BattleGameMode::result_from_scores(&scores).unwrap_or(GameResult::Draw))
+        }
+    }
+
+    /// A waker which records that it has been woken up.
+    struct Woken(AtomicBool);
+
+    impl Wake for Woken {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_player_handlers() {
+        let players = players();
+        let (first, second) =
+            (players.get(PlayerId(1)).unwrap(), players.get(PlayerId(2)).unwrap());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let moves = Moves::GemGrab(&[]);
+
+        let mut handlers = PlayerHandlers::new();
+        handlers.bind(PlayerId(1), LogHandler("first", Arc::clone(&log)));
+        block_on(handlers.event(first, &GameEvent::TimeOut)).unwrap();
+        block_on(handlers.get_move_idx(moves, first, &players)).unwrap();
+
+        // A player without a handler can't be reached till there is a fallback.
+        assert!(matches!(block_on(handlers.info(&second.id, "")), Err(Error::NoUserFound)));
+        let prompt = handlers.get_move_idx(moves, second, &players);
+        assert!(matches!(block_on(prompt), Err(Error::NoUserFound)));
+
+        handlers.set_fallback(LogHandler("fallback", Arc::clone(&log)));
+        block_on(handlers.event(second, &GameEvent::TimeOut)).unwrap();
+        block_on(handlers.info(&first.id, "")).unwrap();
+
+        assert_eq!(*log.lock().unwrap(), [
+            ("first", PlayerId(1)),
+            ("first", PlayerId(1)),
+            ("fallback", PlayerId(2)),
+            ("first", PlayerId(1)),
+        ]);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let game = Game::new(
+            BattleGameMode::new(Event::Heist),
+            players(),
+            AiHandler::new(RandomStrategy, 7),
+        );
+        let cancel = game.cancel.clone();
+        cancel.cancel();
+        assert!(cancel.is_stopped());
+        assert!(matches!(block_on(game.run()), Err(Error::Cancelled)));
+
+        let game = Game::new(
+            BattleGameMode::new(Event::Heist),
+            players(),
+            AiHandler::new(RandomStrategy, 7),
+        );
+        game.cancel.forfeit(PlayerId(4));
+        let result = block_on(game.run()).unwrap();
+        let placements = vec![TeamId(0), TeamId(1)];
+        assert_eq!(result, GameResult::Forfeit { forfeited: TeamId(1), placements });
+        assert_eq!(result.placement(TeamId(1)), Some(2));
+    }
+
+    #[test]
+    fn test_cancel_prompt() {
+        let woken = Arc::new(Woken(AtomicBool::new(false)));
+        let waker = Waker::from(Arc::clone(&woken));
+        let mut cx = Context::from_waker(&waker);
+
+        let game = Game::new(BattleGameMode::new(Event::Heist), players(), AfkHandler);
+        let cancel = game.cancel.clone();
+        let mut run = pin!(game.run());
+        assert!(run.as_mut().poll(&mut cx).is_pending());
+
+        // The game is blocked on the prompt of the first player till it is forfeited.
+        cancel.forfeit(PlayerId(1));
+        assert!(woken.0.load(Ordering::SeqCst));
+        match run.as_mut().poll(&mut cx) {
+            Poll::Ready(result) => assert_eq!(result.unwrap().winner(), Some(TeamId(1))),
+            Poll::Pending => panic!("the game is still waiting for a move"),
+        }
+    }
+
+    #[test]
+    fn test_custom_mode_state() {
+        let mut registry = GameModeRegistry::new();
+        registry.register(Event::GemGrab, Tag);
+
+        let mut players = players();
+        let handler = AiHandler::new(RandomStrategy, 7);
+        let mut rng = rng::seeded(7);
+        let run = registry.run(Event::GemGrab, &mut players, &handler, &mut rng);
+        assert_eq!(block_on(run).unwrap().winner(), Some(TeamId(1)));
+
+        let state = &players.get(PlayerId(4)).unwrap().state;
+        let json = serde_json::to_string(state).unwrap();
+        let state: player::PlayerState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.custom_mode::<TagState>().map(|s| s.tags), Some(2));
+        assert!(state.mode::<battle_game_mode::gemgrab::GemGrabState>().is_none());
+    }
+}
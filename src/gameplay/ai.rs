@@ -0,0 +1,470 @@
+//! Computer-controlled players.
+//!
+//! An [`AiHandler`] chooses moves for the players with a [`Strategy`]. It can be
+//! used on its own to run games between bots, or bound to some of the players with
+//! [`PlayerHandlers`](super::PlayerHandlers) to fill a lobby of human players with bots.
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use rand::Rng;
+
+use super::battle_game_mode::bounty::BountyMove;
+use super::battle_game_mode::brawlball::BrawlBallMove;
+use super::battle_game_mode::gemgrab::GemGrabMove;
+use super::battle_game_mode::heist::HeistMove;
+use super::battle_game_mode::hotzone::HotZoneMove;
+use super::battle_game_mode::showdown::ShowdownMove;
+use super::battle_game_mode::siege::SiegeMove;
+use super::battle_game_mode::GeneralMove;
+use super::player::{Player, PlayerId};
+use super::{GameHandler, Moves, Players};
+use crate::error::Result;
+use crate::utils::rng::{self, GameRng};
+
+/// A way of choosing moves for a computer-controlled player.
+pub trait Strategy: Send + Sync + Debug {
+    /// Returns the index of the chosen move among `moves`.
+    ///
+    /// `player` is the player whose turn it is and `moves` is never empty.
+    fn choose(
+        &self,
+        moves: Moves<'_>,
+        player: &Player,
+        players: &Players,
+        rng: &mut GameRng,
+    ) -> usize;
+}
+
+/// A strategy which chooses any of the possible moves at random.
+///
+/// Like all the built-in strategies, it never surrenders.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn choose(
+        &self,
+        moves: Moves<'_>,
+        _player: &Player,
+        _players: &Players,
+        rng: &mut GameRng,
+    ) -> usize {
+        let playable = playable(moves);
+
+        playable.get(rng.gen_range(0..playable.len().max(1))).copied().unwrap_or(0)
+    }
+}
+
+/// A strategy which chooses the move dealing the most damage.
+///
+/// Moves which can defeat an enemy are preferred over all other moves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn choose(
+        &self,
+        moves: Moves<'_>,
+        player: &Player,
+        players: &Players,
+        rng: &mut GameRng,
+    ) -> usize {
+        best_of(moves, |idx| greedy_score(moves, idx, player, players), rng)
+    }
+}
+
+/// A strategy which plays for the objective of the game mode.
+///
+/// Enemies are only attacked if they can be defeated by the move. Otherwise, the
+/// strategy collects gems in Gem Grab, power-ups in Showdown, bolts in Siege, attacks
+/// the enemy safe in Heist, shoots the ball in Brawl Ball, enters the zone in Hot Zone
+/// and takes the center star in Bounty. It falls back to [`GreedyStrategy`] when there
+/// is no objective to play for.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ObjectiveStrategy;
+
+impl Strategy for ObjectiveStrategy {
+    fn choose(
+        &self,
+        moves: Moves<'_>,
+        player: &Player,
+        players: &Players,
+        rng: &mut GameRng,
+    ) -> usize {
+        let defeats = |idx| general_move(moves, idx).is_some_and(|m| defeats(&m, player, players));
+
+        if (0..moves.len()).any(defeats) {
+            return GreedyStrategy.choose(moves, player, players, rng);
+        }
+
+        if (0..moves.len()).any(|idx| objective_score(moves, idx) > 0) {
+            best_of(moves, |idx| objective_score(moves, idx), rng)
+        } else {
+            GreedyStrategy.choose(moves, player, players, rng)
+        }
+    }
+}
+
+/// Returns the indices of the moves a bot can choose, which are all the moves
+/// except surrendering.
+fn playable(moves: Moves<'_>) -> Vec<usize> {
+    (0..moves.len())
+        .filter(|idx| !matches!(general_move(moves, *idx), Some(GeneralMove::Surrender)))
+        .collect()
+}
+
+/// Returns the index of the playable move with the highest score, choosing randomly
+/// between ties.
+fn best_of<F: Fn(usize) -> u32>(moves: Moves<'_>, score: F, rng: &mut GameRng) -> usize {
+    let scores: Vec<(usize, u32)> =
+        playable(moves).into_iter().map(|idx| (idx, score(idx))).collect();
+    let best = scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+    let ties: Vec<usize> =
+        scores.iter().filter(|(_, score)| *score == best).map(|(idx, _)| *idx).collect();
+
+    if ties.is_empty() {
+        0
+    } else {
+        ties[rng.gen_range(0..ties.len())]
+    }
+}
+
+/// Returns the general move at `idx`, if the move at `idx` is a general move.
+pub(crate) fn general_move(moves: Moves<'_>, idx: usize) -> Option<GeneralMove> {
+    match moves {
+        Moves::GemGrab(moves) => match moves.get(idx)? {
+            GemGrabMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+        Moves::Showdown(moves) => match moves.get(idx)? {
+            ShowdownMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+        Moves::BrawlBall(moves) => match moves.get(idx)? {
+            BrawlBallMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+        Moves::Heist(moves) => match moves.get(idx)? {
+            HeistMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+        Moves::Bounty(moves) => match moves.get(idx)? {
+            BountyMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+        Moves::Siege(moves) => match moves.get(idx)? {
+            SiegeMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+        Moves::HotZone(moves) => match moves.get(idx)? {
+            HotZoneMove::General(gm) => Some(*gm),
+            _ => None,
+        },
+    }
+}
+
+/// Returns the damage dealt by projectiles fired from `range` at a target `distance` away.
+///
+/// This mirrors the damage calculation of [`BrawlerExt::attack`](super::BrawlerExt::attack).
+fn hit_damage(damage: u32, projectiles: u32, range: f32, distance: f32) -> u32 {
+    if range < distance {
+        return 0;
+    }
+
+    let diff = (range - distance).ceil();
+
+    damage * (projectiles as f32 / diff).ceil() as u32
+}
+
+/// Returns the damage the player deals with a normal attack or the super.
+///
+/// `distance` is the distance to the target, `None` if all the projectiles hit.
+fn damage(player: &Player, is_ult: bool, distance: Option<f32>) -> u32 {
+    let brawler = &player.brawler_state.brawler;
+    let level = player.brawler_state.level;
+    let info = brawler.info();
+
+    let (damage, projectiles, range) = if is_ult {
+        (info.ult.damage.unwrap_or(0), info.ult.projectiles, info.ult.range.unwrap_or(0.0))
+    } else {
+        (info.attack.damage, info.attack.projectiles, info.attack.range)
+    };
+    let damage = brawler.buff_stat(damage, level);
+
+    match distance {
+        Some(distance) => hit_damage(damage, projectiles, range, distance),
+        None => damage * projectiles,
+    }
+}
+
+/// Returns the damage a general move deals to its target.
+fn general_damage(gm: &GeneralMove, player: &Player, players: &Players) -> u32 {
+    let target = match gm.target().and_then(|id| players.get(id)) {
+        Some(target) => target,
+        None => return 0,
+    };
+
+    match gm {
+        GeneralMove::Attack(_) => {
+            damage(player, false, Some(player.state.distance_from_player(&target.state)))
+        },
+        GeneralMove::Ult(_) => {
+            let brawler = &player.brawler_state.brawler;
+
+            if brawler.has_spawn() {
+                // The summoned spawn attacks once per round.
+                brawler.spawn(player.brawler_state.level).map_or(0, |s| s.info.damage)
+            } else {
+                damage(player, true, Some(player.state.distance_from_player(&target.state)))
+            }
+        },
+        GeneralMove::AttackSpawn(_) | GeneralMove::UltSpawn(_) => {
+            let health = target.state.spawn.as_ref().map_or(0, |s| s.health);
+
+            damage(player, matches!(gm, GeneralMove::UltSpawn(_)), None).min(health)
+        },
+        // The effects of Gadgets are defined by the Brawlers, so they can't be predicted.
+        GeneralMove::UseGadget
+        | GeneralMove::MoveTowards(_)
+        | GeneralMove::Retreat(_)
+        | GeneralMove::Strafe(_)
+        | GeneralMove::Dodge
+        | GeneralMove::Surrender => 0,
+    }
+}
+
+/// Checks if a general move defeats its target.
+fn defeats(gm: &GeneralMove, player: &Player, players: &Players) -> bool {
+    let target = match gm {
+        GeneralMove::Attack(target) => players.get(*target),
+        // A summoned spawn doesn't attack till the next turn of its owner.
+        GeneralMove::Ult(target) if !player.brawler_state.brawler.has_spawn() => {
+            players.get(*target)
+        },
+        _ => None,
+    };
+
+    target.is_some_and(|t| {
+        let damage = general_damage(gm, player, players);
+        damage > 0 && damage >= t.state.health
+    })
+}
+
+/// Checks if an enemy is out of the range or the line of sight of the player's attack.
+fn out_of_range(player: &Player, target: PlayerId, players: &Players) -> bool {
+    let range = player.brawler_state.brawler.info().attack.range;
+    let (from, arena) = (&player.state.position, players.arena());
+
+    players.get(target).is_some_and(|t| {
+        player.state.distance_from_player(&t.state) > range
+            || !arena.has_line_of_sight(from, &t.state.position)
+    })
+}
+
+/// Returns the score of a move for [`GreedyStrategy`].
+///
+/// The score is the damage dealt by the move, doubled if it defeats an enemy. Moving
+/// towards an enemy out of range or sight scores `1`, so that the player closes in
+/// when no attack can deal damage.
+fn greedy_score(moves: Moves<'_>, idx: usize, player: &Player, players: &Players) -> u32 {
+    if let Some(GeneralMove::MoveTowards(target)) = general_move(moves, idx) {
+        return u32::from(out_of_range(player, target, players));
+    }
+
+    if let Some(gm) = general_move(moves, idx) {
+        let damage = general_damage(&gm, player, players);
+
+        return if defeats(&gm, player, players) { damage.saturating_mul(2) } else { damage };
+    }
+
+    // Safes and siege robots are too big to miss.
+    match moves {
+        Moves::Heist(moves) => match moves.get(idx) {
+            Some(HeistMove::AttackSafe(_)) => damage(player, false, None),
+            Some(HeistMove::UltSafe(_)) => damage(player, true, None),
+            _ => 0,
+        },
+        Moves::Siege(moves) => match moves.get(idx) {
+            Some(SiegeMove::AttackRobot(_)) => damage(player, false, None),
+            Some(SiegeMove::UltRobot(_)) => damage(player, true, None),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Returns the score of a move for [`ObjectiveStrategy`].
+///
+/// Moves which don't play for the objective have a score of `0`.
+fn objective_score(moves: Moves<'_>, idx: usize) -> u32 {
+    match moves {
+        Moves::GemGrab(moves) => match moves.get(idx) {
+            Some(GemGrabMove::CollectDroppedGems) => 2,
+            Some(GemGrabMove::CollectGem | GemGrabMove::MoveToMine) => 1,
+            _ => 0,
+        },
+        Moves::Showdown(moves) => match moves.get(idx) {
+            Some(ShowdownMove::CollectPowerUp) => 1,
+            _ => 0,
+        },
+        Moves::BrawlBall(moves) => match moves.get(idx) {
+            Some(BrawlBallMove::UltShootBall) => 3,
+            Some(BrawlBallMove::ShootBall) => 2,
+            Some(
+                BrawlBallMove::PickUpBall | BrawlBallMove::MoveToBall | BrawlBallMove::Dribble,
+            ) => 1,
+            _ => 0,
+        },
+        Moves::Heist(moves) => match moves.get(idx) {
+            Some(HeistMove::UltSafe(_)) => 2,
+            Some(HeistMove::AttackSafe(_) | HeistMove::MoveToSafe(_)) => 1,
+            _ => 0,
+        },
+        Moves::Siege(moves) => match moves.get(idx) {
+            Some(SiegeMove::CollectBolt) => 1,
+            _ => 0,
+        },
+        Moves::HotZone(moves) => match moves.get(idx) {
+            Some(HotZoneMove::EnterZone) => 1,
+            _ => 0,
+        },
+        Moves::Bounty(moves) => match moves.get(idx) {
+            Some(BountyMove::CollectCenterStar) => 1,
+            _ => 0,
+        },
+    }
+}
+
+/// A [`GameHandler`] which plays for all the players with a [`Strategy`].
+///
+/// All the messages and events dispatched to it are discarded.
+#[derive(Debug)]
+pub struct AiHandler<S> {
+    /// The strategy used to choose the moves.
+    strategy: S,
+    /// The random number generator used by the strategy.
+    rng: Mutex<GameRng>,
+}
+
+impl<S: Strategy> AiHandler<S> {
+    /// Creates a new [`AiHandler`] which chooses moves with `strategy`.
+    ///
+    /// `seed` is the seed of the random number generator used by the strategy.
+    pub fn new(strategy: S, seed: u64) -> Self {
+        Self { strategy, rng: Mutex::new(rng::seeded(seed)) }
+    }
+}
+
+#[async_trait]
+impl<S: Strategy> GameHandler for AiHandler<S> {
+    async fn info(&self, _player_id: &PlayerId, _msg: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        moves: Moves<'a>,
+        player: &Player,
+        players: &Players,
+    ) -> Result<usize> {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+
+        Ok(self.strategy.choose(moves, player, players, &mut rng))
+    }
+}
+
+#[cfg(test)]
+mod test_ai {
+    use super::*;
+    use crate::gameplay::player::{Position, TeamId};
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::gameplay::{BattleGameMode, Game, PlayerHandlers};
+    use crate::model::game_mode::Event;
+
+    /// Returns the players with player 1 next to player 4, ready to use their super.
+    fn face_off() -> Players {
+        let mut players = players();
+        players.get_mut(PlayerId(1)).unwrap().state.position = Position::new(7, 7);
+        players.get_mut(PlayerId(1)).unwrap().state.attacks = 3;
+        players.get_mut(PlayerId(4)).unwrap().state.position = Position::new(7, 8);
+
+        players
+    }
+
+    #[test]
+    fn test_greedy() {
+        let players = face_off();
+        let player = players.get(PlayerId(1)).unwrap();
+        let mut rng = rng::seeded(0);
+
+        let moves = [
+            GemGrabMove::General(GeneralMove::Dodge),
+            GemGrabMove::General(GeneralMove::Attack(PlayerId(4))),
+            GemGrabMove::General(GeneralMove::Ult(PlayerId(4))),
+            GemGrabMove::General(GeneralMove::Surrender),
+        ];
+        let choice = GreedyStrategy.choose(Moves::GemGrab(&moves), player, &players, &mut rng);
+        assert_eq!(choice, 2);
+
+        // The super can't be used, so the attack deals the most damage.
+        let choice = GreedyStrategy.choose(Moves::GemGrab(&moves[..2]), player, &players, &mut rng);
+        assert_eq!(choice, 1);
+    }
+
+    #[test]
+    fn test_objective() {
+        let mut players = face_off();
+        let mut rng = rng::seeded(0);
+
+        let attack = GeneralMove::Attack(PlayerId(4));
+        let moves = [GemGrabMove::General(attack), GemGrabMove::CollectGem];
+        let player = players.get(PlayerId(1)).unwrap();
+        let choice = ObjectiveStrategy.choose(Moves::GemGrab(&moves), player, &players, &mut rng);
+        assert_eq!(choice, 1);
+
+        let moves =
+            [HeistMove::General(attack), HeistMove::DefendSafe, HeistMove::AttackSafe(TeamId(1))];
+        let choice = ObjectiveStrategy.choose(Moves::Heist(&moves), player, &players, &mut rng);
+        assert_eq!(choice, 2);
+
+        let moves = [HotZoneMove::General(attack), HotZoneMove::LeaveZone, HotZoneMove::EnterZone];
+        let choice = ObjectiveStrategy.choose(Moves::HotZone(&moves), player, &players, &mut rng);
+        assert_eq!(choice, 2);
+
+        let moves = [BountyMove::General(attack), BountyMove::CollectCenterStar];
+        let choice = ObjectiveStrategy.choose(Moves::Bounty(&moves), player, &players, &mut rng);
+        assert_eq!(choice, 1);
+
+        // An enemy who can be defeated is attacked instead.
+        players.get_mut(PlayerId(4)).unwrap().state.health = 100;
+        let player = players.get(PlayerId(1)).unwrap();
+        let moves = [GemGrabMove::General(attack), GemGrabMove::CollectGem];
+        let choice = ObjectiveStrategy.choose(Moves::GemGrab(&moves), player, &players, &mut rng);
+        assert_eq!(choice, 0);
+    }
+
+    #[test]
+    fn test_strategies() {
+        let events = [
+            Event::GemGrab,
+            Event::Showdown,
+            Event::BrawlBall,
+            Event::Heist,
+            Event::Bounty,
+            Event::Siege,
+            Event::HotZone,
+        ];
+
+        for event in events {
+            let handler = AiHandler::new(GreedyStrategy, 1);
+            block_on(Game::new(BattleGameMode::new(event), players(), handler).run()).unwrap();
+
+            let mut handler = PlayerHandlers::new();
+            handler.bind(PlayerId(4), AiHandler::new(ObjectiveStrategy, 2));
+            handler.set_fallback(AiHandler::new(RandomStrategy, 3));
+            block_on(Game::new(BattleGameMode::new(event), players(), handler).run()).unwrap();
+        }
+    }
+}
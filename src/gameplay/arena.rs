@@ -0,0 +1,345 @@
+//! The map the players move on during a game.
+//!
+//! An [`Arena`] is a bounded grid of tiles. Every team starts at its spawn point and
+//! the players move across the grid with the movement moves of
+//! [`GeneralMove`](super::battle_game_mode::GeneralMove), which makes the range of
+//! the attacks matter. An arena created from a [`Map`] has walls blocking the
+//! movement and the attacks, bushes hiding the players and the tiles where the
+//! objectives of the game modes stand.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::player::{Position, TeamId};
+use crate::model::map::{Map, Tile};
+
+/// Width of the default arena, in tiles.
+const DEFAULT_WIDTH: u32 = 15;
+/// Height of the default arena, in tiles.
+const DEFAULT_HEIGHT: u32 = 15;
+/// Number of tiles from which a player in a bush is revealed to the enemies.
+const REVEAL_RANGE: u32 = 1;
+
+/// Represents the grid the players of a game move on.
+///
+/// The spawn point of a team can be set with [`set_spawn`](Self::set_spawn). Teams
+/// without a spawn point are spread evenly along an ellipse inscribed in the arena,
+/// with the first team at the center of the bottom edge, and moved to the nearest
+/// walkable tile.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Arena {
+    /// Number of tiles along the x axis.
+    pub width: u32,
+    /// Number of tiles along the y axis.
+    pub height: u32,
+    /// The spawn points set for the teams.
+    spawns: HashMap<TeamId, Position>,
+    /// The map of the arena, if any.
+    map: Option<Map>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+}
+
+impl Arena {
+    /// Creates a new [`Arena`] with given dimensions, which are at least one tile.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width: width.max(1), height: height.max(1), spawns: HashMap::new(), map: None }
+    }
+
+    /// Creates a new [`Arena`] from a map.
+    ///
+    /// The arena has the dimensions of the map and the teams spawn at the spawn points
+    /// of the map.
+    pub fn from_map(map: Map) -> Self {
+        let mut arena = Self::new(map.width(), map.height());
+        for (team, (x, y)) in map.spawns() {
+            arena.set_spawn(TeamId(team), Position::new(x, y));
+        }
+
+        arena.map = Some(map);
+        arena
+    }
+
+    /// Returns the map of the arena, if any.
+    pub fn map(&self) -> Option<&Map> {
+        self.map.as_ref()
+    }
+
+    /// Returns the tile at a position.
+    ///
+    /// Every tile of an arena without a map is [`Tile::Empty`].
+    pub fn tile(&self, position: &Position) -> Tile {
+        self.map.as_ref().and_then(|map| map.tile(position.x(), position.y())).unwrap_or_default()
+    }
+
+    /// Checks if there is a line of sight between two positions.
+    ///
+    /// See [`Map::has_line_of_sight`] for more info.
+    pub fn has_line_of_sight(&self, from: &Position, to: &Position) -> bool {
+        self.map
+            .as_ref()
+            .is_none_or(|map| map.has_line_of_sight((from.x(), from.y()), (to.x(), to.y())))
+    }
+
+    /// Checks if a player at `position` is hidden from enemies at `observers`.
+    ///
+    /// A player is hidden if they stand in a bush and no observer is close to them.
+    pub fn is_hidden(&self, position: &Position, observers: &[Position]) -> bool {
+        self.tile(position) == Tile::Bush
+            && observers.iter().all(|o| o.tiles_from(position) > REVEAL_RANGE)
+    }
+
+    /// Sets the spawn point of a team, moved inside the arena if it is outside.
+    pub fn set_spawn(&mut self, team: TeamId, position: Position) -> &mut Self {
+        self.spawns.insert(team, self.clamp(position));
+        self
+    }
+
+    /// Checks if a position is inside the arena.
+    pub fn contains(&self, position: &Position) -> bool {
+        position.x() < self.width && position.y() < self.height
+    }
+
+    /// Returns the position closest to `position` which is inside the arena.
+    pub fn clamp(&self, position: Position) -> Position {
+        Position::new(position.x().min(self.width - 1), position.y().min(self.height - 1))
+    }
+
+    /// Returns the walkable tile nearest to `position`, which is moved inside the arena
+    /// first.
+    ///
+    /// The tiles are searched in rings around the position, row by row. Returns the
+    /// position inside the arena if no tile is walkable.
+    pub fn nearest_walkable(&self, position: Position) -> Position {
+        let position = self.clamp(position);
+        let radius = i64::from(self.width.max(self.height));
+
+        (0..radius)
+            .flat_map(|r| {
+                (-r..=r).flat_map(move |dy| {
+                    (-r..=r)
+                        .filter(move |dx| dx.abs() == r || dy.abs() == r)
+                        .map(move |dx| (dx, dy))
+                })
+            })
+            .find_map(|offset| self.offset(position, offset))
+            .unwrap_or(position)
+    }
+
+    /// Returns the center of the arena, or the walkable tile nearest to it.
+    pub fn center(&self) -> Position {
+        self.nearest_walkable(Position::new(self.width / 2, self.height / 2))
+    }
+
+    /// Returns the tile of kind `tile` nearest to `position`, `None` if the arena has no
+    /// map or the map has no such tile.
+    pub fn nearest_tile(&self, tile: Tile, position: &Position) -> Option<Position> {
+        let map = self.map.as_ref()?;
+
+        map.find(tile)
+            .map(|(x, y)| Position::new(x, y))
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+    }
+
+    /// Returns the position of the gem mine of Gem Grab.
+    ///
+    /// It is the [`Tile::GemMine`] nearest to the center of the map, or the center of
+    /// the arena if there is none.
+    pub fn gem_mine(&self) -> Position {
+        let center = self.center();
+
+        self.nearest_tile(Tile::GemMine, &center).unwrap_or(center)
+    }
+
+    /// Returns the position of the structure of a team spawning at `spawn`, like its safe
+    /// in Heist or its goal in Brawl Ball.
+    ///
+    /// It is the tile of kind `tile` nearest to `spawn`, or the tile
+    /// [`behind`](Self::behind) `spawn` if the map has no such tile.
+    pub fn structure(&self, tile: Tile, spawn: Position) -> Position {
+        self.nearest_tile(tile, &spawn).unwrap_or_else(|| self.behind(spawn))
+    }
+
+    /// Returns the tile behind `spawn`, away from the center of the arena.
+    ///
+    /// This is where the structures of a team stand on a map without their tiles.
+    /// Returns `spawn` itself if there is no walkable tile behind it.
+    pub fn behind(&self, spawn: Position) -> Position {
+        self.retreat(spawn, self.center(), 1)
+    }
+
+    /// Returns the spawn point of the team at index `idx` among `teams` teams.
+    pub fn spawn(&self, team: TeamId, idx: usize, teams: usize) -> Position {
+        if let Some(spawn) = self.spawns.get(&team) {
+            return *spawn;
+        }
+
+        let (center_x, center_y) = ((self.width / 2) as f32, (self.height / 2) as f32);
+        let angle = 2.0 * PI * idx as f32 / teams.max(1) as f32;
+        let x = center_x - center_x * angle.sin();
+        let y = center_y - center_y * angle.cos();
+
+        self.nearest_walkable(Position::new(x.round() as u32, y.round() as u32))
+    }
+
+    /// Returns the position of the player at index `slot` of a team of `size` players
+    /// spawning at `spawn`.
+    ///
+    /// The players of a team stand next to each other along the x axis.
+    pub fn spawn_slot(&self, spawn: Position, slot: usize, size: usize) -> Position {
+        let offset = slot as i64 - (size.saturating_sub(1) / 2) as i64;
+
+        self.offset(spawn, (offset, 0)).unwrap_or(spawn)
+    }
+
+    /// Moves from `from` towards `to` by at most `tiles` tiles.
+    ///
+    /// Every tile moved is a step along both the axes. The move stops next to `to`
+    /// instead of on it, or at the first obstacle on the way.
+    pub fn move_towards(&self, from: Position, to: Position, tiles: u32) -> Position {
+        let mut position = from;
+
+        for _ in 0..tiles {
+            if position.tiles_from(&to) <= 1 {
+                break;
+            }
+
+            match self.offset(position, position.direction(&to)) {
+                Some(next) => position = next,
+                None => break,
+            }
+        }
+
+        position
+    }
+
+    /// Moves from `from` away from `to` by at most `tiles` tiles, stopping at the edges
+    /// and obstacles.
+    pub fn retreat(&self, from: Position, to: Position, tiles: u32) -> Position {
+        let direction = match to.direction(&from) {
+            (0, 0) => (0, -1),
+            direction => direction,
+        };
+
+        self.walk(from, direction, tiles)
+    }
+
+    /// Moves from `from` sideways, keeping the same direction to `to`, by at most
+    /// `tiles` tiles.
+    ///
+    /// The move goes the other way if the arena ends or there is an obstacle on the
+    /// first side.
+    pub fn strafe(&self, from: Position, to: Position, tiles: u32) -> Position {
+        let direction = match from.direction(&to) {
+            (0, 0) => (1, 0),
+            (x, y) => (-y, x),
+        };
+
+        match self.offset(from, direction) {
+            Some(_) => self.walk(from, direction, tiles),
+            None => self.walk(from, (-direction.0, -direction.1), tiles),
+        }
+    }
+
+    /// Moves from `from` in `direction` by at most `tiles` tiles, stopping at the edges
+    /// and obstacles.
+    fn walk(&self, from: Position, direction: (i64, i64), tiles: u32) -> Position {
+        let mut position = from;
+
+        for _ in 0..tiles {
+            match self.offset(position, direction) {
+                Some(next) => position = next,
+                None => break,
+            }
+        }
+
+        position
+    }
+
+    /// Returns `position` moved by `offset`, `None` if it ends up outside the arena or
+    /// on a tile the players can't move onto.
+    fn offset(&self, position: Position, offset: (i64, i64)) -> Option<Position> {
+        let x = u32::try_from(i64::from(position.x()) + offset.0).ok()?;
+        let y = u32::try_from(i64::from(position.y()) + offset.1).ok()?;
+        let position = Position::new(x, y);
+
+        (self.contains(&position) && self.tile(&position).is_walkable()).then_some(position)
+    }
+}
+
+#[cfg(test)]
+mod test_arena {
+    use super::*;
+
+    #[test]
+    fn test_moves() {
+        let mut arena = Arena::new(5, 9);
+        assert_eq!(arena.spawn(TeamId(0), 0, 2), Position::new(2, 0));
+        assert_eq!(arena.spawn(TeamId(1), 1, 2), Position::new(2, 8));
+
+        arena.set_spawn(TeamId(1), Position::new(10, 10));
+        assert_eq!(arena.spawn(TeamId(1), 1, 2), Position::new(4, 8));
+        assert_eq!(arena.spawn_slot(Position::new(4, 8), 2, 3), Position::new(4, 8));
+        let json = serde_json::to_string(&arena).unwrap();
+        assert_eq!(arena, serde_json::from_str(&json).unwrap());
+
+        let (from, to) = (Position::new(0, 0), Position::new(3, 8));
+        assert_eq!(arena.move_towards(from, to, 2), Position::new(2, 2));
+        assert_eq!(arena.move_towards(from, to, 20), Position::new(3, 7));
+        assert_eq!(arena.retreat(Position::new(2, 2), to, 5), Position::new(0, 0));
+        assert_eq!(arena.strafe(Position::new(0, 4), Position::new(0, 8), 2), Position::new(2, 4));
+    }
+
+    #[test]
+    fn test_map() {
+        let map = Map::from_ascii("Skull Creek", "..1..\n.###.\n*....\n..0..").unwrap();
+        let arena = Arena::from_map(map);
+
+        assert_eq!((arena.width, arena.height), (5, 4));
+        assert_eq!(arena.spawn(TeamId(1), 1, 2), Position::new(2, 0));
+
+        let (from, to) = (Position::new(2, 3), Position::new(2, 0));
+        assert!(!arena.has_line_of_sight(&from, &to));
+        assert_eq!(arena.move_towards(from, to, 3), Position::new(2, 2));
+
+        let bush = Position::new(0, 2);
+        assert!(arena.is_hidden(&bush, &[from]));
+        assert!(!arena.is_hidden(&bush, &[Position::new(1, 3)]));
+    }
+
+    #[test]
+    fn test_nearest_walkable() {
+        let map = Map::from_ascii("Pillars", "..#..\n.....\n..#..\n.....\n..#..").unwrap();
+        let arena = Arena::from_map(map);
+
+        assert_eq!(arena.center(), Position::new(1, 1));
+        assert_eq!(arena.spawn(TeamId(0), 0, 2), Position::new(1, 0));
+        assert_eq!(arena.spawn(TeamId(1), 1, 2), Position::new(1, 3));
+        assert_eq!(arena.nearest_walkable(Position::new(9, 9)), Position::new(4, 4));
+    }
+
+    #[test]
+    fn test_structures() {
+        let map = Map::from_ascii("Pit Stop", ".S1G.\n.....\n..M..\n.....\nG.0S.").unwrap();
+        let arena = Arena::from_map(map);
+        let spawn = arena.spawn(TeamId(0), 0, 2);
+
+        assert_eq!(arena.gem_mine(), Position::new(2, 2));
+        assert_eq!(arena.structure(Tile::Safe, spawn), Position::new(3, 4));
+        assert_eq!(arena.structure(Tile::Goal, spawn), Position::new(0, 4));
+        assert!(!arena.tile(&Position::new(3, 4)).is_walkable());
+
+        // An arena without a map has its gem mine at the center and the structures
+        // behind the spawn points.
+        let arena = Arena::new(5, 5);
+        assert_eq!(arena.gem_mine(), Position::new(2, 2));
+        assert_eq!(arena.structure(Tile::Safe, Position::new(2, 3)), Position::new(2, 4));
+    }
+}
@@ -4,11 +4,14 @@
 pub mod defaults;
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use rand::{thread_rng, Rng};
+use rand::{Rng, RngCore};
 use serde::{Deserialize, Serialize};
 
-use super::player::PlayerState;
+use self::defaults::{Nita, Shelly};
+use super::player::{PlayerSpawn, PlayerState};
+use crate::error::{Error, Result};
 
 /// Extension trait for Brawlers that adds all the functionality to them.
 ///
@@ -65,17 +68,31 @@ pub trait BrawlerExt: Send + Sync + std::fmt::Debug {
 
         let projectiles = (attack.projectiles as f32 / diff).ceil() as u32;
 
+        let damage = with_star_power(first, attack_damage * projectiles, |slot, damage| {
+            self.star_power_damage(slot, DamageSource::Attack, damage, first, Some(second))
+        });
+
         // Reduce the enemy's health.
-        second.health -= attack_damage * projectiles;
+        second.damage(damage);
 
-        // Decrease ammo.
+        // Decrease ammo and charge the super.
         first.ammo -= 1;
+        first.attacks +=
+            with_star_power(first, 1, |slot, hits| self.star_power_super_charge(slot, hits, first));
     }
 
     /// Performs Brawler's super.
     ///
-    /// `first` is the attacker, `second` is getting attacked.
+    /// `first` is the attacker, `second` is getting attacked. If the Brawler has a
+    /// spawn, the super summons the spawn for `first` instead of attacking.
     fn ult(&self, first: &mut PlayerState, second: &mut PlayerState, first_brawler_level: u32) {
+        if self.has_spawn() {
+            // A new spawn replaces the previous one.
+            first.spawn = self.spawn(first_brawler_level);
+            first.attacks = 0;
+            return;
+        }
+
         let ult = &self.info().ult;
 
         let ult_damage = self.buff_stat(ult.damage.unwrap_or(0), first_brawler_level);
@@ -94,15 +111,19 @@ pub trait BrawlerExt: Send + Sync + std::fmt::Debug {
 
         let projectiles = (ult.projectiles as f32 / diff).ceil() as u32;
 
+        let damage = with_star_power(first, ult_damage * projectiles, |slot, damage| {
+            self.star_power_damage(slot, DamageSource::Ult, damage, first, Some(second))
+        });
+
         // Reduce the enemy's health.
-        second.health -= ult_damage * projectiles;
+        second.damage(damage);
 
         // Reset attacks count.
         first.attacks = 0;
     }
 
-    fn chance_calculation(&self, raw: u32) -> u32 {
-        let chance: u32 = thread_rng().gen_range(0..11);
+    fn chance_calculation(&self, raw: u32, rng: &mut dyn RngCore) -> u32 {
+        let chance: u32 = rng.gen_range(0..11);
 
         if chance >= 9 {
             raw
@@ -139,20 +160,243 @@ pub trait BrawlerExt: Send + Sync + std::fmt::Debug {
         self.info().ult.hits_required
     }
 
+    /// Performs Brawler's attack on the spawn of another player.
+    ///
+    /// `first` is the attacker, `second` owns the spawn getting attacked. Spawns can't
+    /// dodge, so all the projectiles hit. The spawn is removed once it dies.
+    fn attack_spawn(
+        &self,
+        first: &mut PlayerState,
+        second: &mut PlayerState,
+        first_brawler_level: u32,
+    ) {
+        let attack = &self.info().attack;
+
+        let damage = self.buff_stat(attack.damage, first_brawler_level) * attack.projectiles;
+        let damage = with_star_power(first, damage, |slot, damage| {
+            self.star_power_damage(slot, DamageSource::Attack, damage, first, Some(second))
+        });
+        damage_spawn(second, damage);
+
+        first.ammo -= 1;
+        first.attacks +=
+            with_star_power(first, 1, |slot, hits| self.star_power_super_charge(slot, hits, first));
+    }
+
+    /// Performs Brawler's super on the spawn of another player.
+    ///
+    /// `first` is the attacker, `second` owns the spawn getting attacked.
+    fn ult_spawn(
+        &self,
+        first: &mut PlayerState,
+        second: &mut PlayerState,
+        first_brawler_level: u32,
+    ) {
+        let ult = &self.info().ult;
+
+        let damage = self.buff_stat(ult.damage.unwrap_or(0), first_brawler_level) * ult.projectiles;
+        let damage = with_star_power(first, damage, |slot, damage| {
+            self.star_power_damage(slot, DamageSource::Ult, damage, first, Some(second))
+        });
+        damage_spawn(second, damage);
+
+        first.attacks = 0;
+    }
+
+    /// Performs Brawler's attack on a structure, like a Heist safe or a Siege robot.
+    ///
+    /// `first` is the attacker. Structures can't dodge, so all the projectiles hit.
+    /// Returns the damage dealt to the structure.
+    fn attack_structure(&self, first: &mut PlayerState, first_brawler_level: u32) -> u32 {
+        let attack = &self.info().attack;
+
+        let damage = self.buff_stat(attack.damage, first_brawler_level) * attack.projectiles;
+        let damage = with_star_power(first, damage, |slot, damage| {
+            self.star_power_damage(slot, DamageSource::Attack, damage, first, None)
+        });
+
+        first.ammo -= 1;
+        first.attacks +=
+            with_star_power(first, 1, |slot, hits| self.star_power_super_charge(slot, hits, first));
+
+        damage
+    }
+
+    /// Performs Brawler's super on a structure, like a Heist safe or a Siege robot.
+    ///
+    /// `first` is the attacker. Returns the damage dealt to the structure, which is `0`
+    /// for Brawlers with special SUPER.
+    fn ult_structure(&self, first: &mut PlayerState, first_brawler_level: u32) -> u32 {
+        let ult = &self.info().ult;
+
+        let damage = self.buff_stat(ult.damage.unwrap_or(0), first_brawler_level) * ult.projectiles;
+        let damage = with_star_power(first, damage, |slot, damage| {
+            self.star_power_damage(slot, DamageSource::Ult, damage, first, None)
+        });
+
+        first.attacks = 0;
+
+        damage
+    }
+
+    /// Performs the effect of the Brawler's Gadget in `slot`.
+    ///
+    /// `player` is the player using the Gadget. The charge of the Gadget is consumed
+    /// before this method is called.
+    ///
+    /// Gadgets have no effect by default. Override this method to define the effects
+    /// of the Brawler's Gadgets.
+    fn gadget(&self, _slot: GadgetSlot, _player: &mut PlayerState, _brawler_level: u32) {}
+
+    /// Modifies the damage dealt by the player with the Brawler's Star Power in `slot`.
+    ///
+    /// `first` is the attacker, `second` is getting attacked or owns the spawn getting
+    /// attacked. `second` is `None` when a structure, like a Heist safe, is attacked.
+    /// The damage is not modified by default.
+    fn star_power_damage(
+        &self,
+        _slot: StarPowerSlot,
+        _source: DamageSource,
+        damage: u32,
+        _first: &PlayerState,
+        _second: Option<&PlayerState>,
+    ) -> u32 {
+        damage
+    }
+
+    /// Modifies the health recovered by the player over time with the Brawler's Star
+    /// Power in `slot`.
+    ///
+    /// The health is not modified by default.
+    fn star_power_heal(&self, _slot: StarPowerSlot, amount: u32, _player: &PlayerState) -> u32 {
+        amount
+    }
+
+    /// Modifies the reload speed of the player's attack, in rounds, with the Brawler's
+    /// Star Power in `slot`.
+    ///
+    /// The reload speed is not modified by default.
+    fn star_power_reload(&self, _slot: StarPowerSlot, reload: f32, _player: &PlayerState) -> f32 {
+        reload
+    }
+
+    /// Modifies the number of hits charged for the SUPER by a successful attack with the
+    /// Brawler's Star Power in `slot`.
+    ///
+    /// The hits are not modified by default.
+    fn star_power_super_charge(
+        &self,
+        _slot: StarPowerSlot,
+        hits: u32,
+        _player: &PlayerState,
+    ) -> u32 {
+        hits
+    }
+
     /// Whether the Brawler has a spawn or not.
     ///
-    /// It is set to false by default.
+    /// It is `true` if the Brawler's SUPER has a spawn.
     fn has_spawn(&self) -> bool {
-        false
+        self.info().ult.spawn.is_some()
     }
 
-    /// Brawler's spawn attack.
-    fn spawn(&self, _level: u32) {
-        if !self.has_spawn() {
-            return;
+    /// Returns Brawler's spawn at the specified level.
+    ///
+    /// Returns `None` if the Brawler doesn't have a spawn.
+    fn spawn(&self, level: u32) -> Option<PlayerSpawn> {
+        let spawn = self.info().ult.spawn.as_ref()?;
+
+        let mut info = spawn.clone();
+        info.health = self.buff_stat(spawn.health, level);
+        info.damage = self.buff_stat(spawn.damage, level);
+
+        Some(PlayerSpawn::new(info))
+    }
+}
+
+/// Modifies `value` with the Star Power active for the player, if any.
+pub(crate) fn with_star_power<T>(
+    player: &PlayerState,
+    value: T,
+    modify: impl FnOnce(StarPowerSlot, T) -> T,
+) -> T {
+    match player.star_power {
+        Some(slot) => modify(slot, value),
+        None => value,
+    }
+}
+
+/// A function which creates a Brawler implementation from the Brawler's info.
+pub type BrawlerConstructor = fn(BrawlerInfo) -> Arc<dyn BrawlerExt>;
+
+/// A collection of Brawler implementations, looked up by the name of the Brawler.
+///
+/// It is used to restore the Brawlers of players from their [`BrawlerInfo`], for example,
+/// when resuming a game from a [`Snapshot`](crate::gameplay::snapshot::Snapshot).
+///
+/// The default registry contains the default implementations of all Brawlers in
+/// [`defaults`].
+#[derive(Clone, Debug)]
+pub struct BrawlerRegistry(HashMap<String, BrawlerConstructor>);
+
+impl BrawlerRegistry {
+    /// Creates a new, empty [`BrawlerRegistry`].
+    ///
+    /// Use [`BrawlerRegistry::default`] to create a registry with the default Brawlers.
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Registers a Brawler implementation for the Brawler with given name.
+    ///
+    /// Returns the previously registered implementation for the name, if any.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        constructor: BrawlerConstructor,
+    ) -> Option<BrawlerConstructor> {
+        self.0.insert(name.into(), constructor)
+    }
+
+    /// Removes the Brawler implementation registered for the name.
+    pub fn unregister(&mut self, name: &str) -> Option<BrawlerConstructor> {
+        self.0.remove(name)
+    }
+
+    /// Checks if a Brawler implementation is registered for the name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Creates the Brawler implementation registered for the name in `info`.
+    ///
+    /// Returns [`Error::UnregisteredBrawler`] if no implementation is registered for it.
+    pub fn build(&self, info: BrawlerInfo) -> Result<Arc<dyn BrawlerExt>> {
+        match self.0.get(&info.name) {
+            Some(constructor) => Ok(constructor(info)),
+            None => Err(Error::UnregisteredBrawler(info.name)),
         }
+    }
+}
+
+impl Default for BrawlerRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register("Shelly", |data| Arc::new(Shelly { data }));
+        registry.register("Nita", |data| Arc::new(Nita { data }));
 
-        todo!()
+        registry
+    }
+}
+
+/// Applies `amount` damage to the spawn of a player, removing the spawn if it dies.
+fn damage_spawn(owner: &mut PlayerState, amount: u32) {
+    if let Some(spawn) = owner.spawn.as_mut() {
+        spawn.damage(amount);
+
+        if spawn.is_dead() {
+            owner.spawn = None;
+        }
     }
 }
 
@@ -161,6 +405,11 @@ const fn default_ammo() -> u8 {
     3
 }
 
+/// Default number of times a Gadget can be used in a match.
+const fn default_charges() -> u8 {
+    3
+}
+
 /// Represents a battle Brawler's info.
 ///
 /// See [`BrawlerExt`] for all methods available for battle Brawlers.
@@ -177,6 +426,36 @@ pub struct BrawlerInfo {
     pub attack: BattleAttack,
     /// Brawler's SUPER ([`BattleUlt`]) at level 1.
     pub ult: BattleUlt,
+    /// Brawler's first [`BattleGadget`], if any.
+    #[serde(default)]
+    pub gadget1: Option<BattleGadget>,
+    /// Brawler's second [`BattleGadget`], if any.
+    #[serde(default)]
+    pub gadget2: Option<BattleGadget>,
+    /// Brawler's first [`BattleStarPower`], if any.
+    #[serde(default)]
+    pub sp1: Option<BattleStarPower>,
+    /// Brawler's second [`BattleStarPower`], if any.
+    #[serde(default)]
+    pub sp2: Option<BattleStarPower>,
+}
+
+impl BrawlerInfo {
+    /// Returns the Brawler's Gadget in `slot`, if any.
+    pub fn gadget(&self, slot: GadgetSlot) -> Option<&BattleGadget> {
+        match slot {
+            GadgetSlot::First => self.gadget1.as_ref(),
+            GadgetSlot::Second => self.gadget2.as_ref(),
+        }
+    }
+
+    /// Returns the Brawler's Star Power in `slot`, if any.
+    pub fn star_power(&self, slot: StarPowerSlot) -> Option<&BattleStarPower> {
+        match slot {
+            StarPowerSlot::First => self.sp1.as_ref(),
+            StarPowerSlot::Second => self.sp2.as_ref(),
+        }
+    }
 }
 
 /// Represents the attack of a Brawler.
@@ -224,6 +503,66 @@ pub struct BattleUlt {
     pub spawn: Option<Spawn>,
 }
 
+/// Represents a Brawler's Gadget in battle.
+///
+/// The effect of the Gadget is defined by [`BrawlerExt::gadget`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct BattleGadget {
+    /// Name of the Gadget.
+    pub name: String,
+    /// Description of the Gadget.
+    pub description: String,
+    /// Number of times the Gadget can be used in a match.
+    ///
+    /// Defaults to 3.
+    #[serde(default = "default_charges")]
+    pub charges: u8,
+}
+
+/// The slot of a Brawler's Gadget.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum GadgetSlot {
+    /// The Brawler's first Gadget.
+    First,
+    /// The Brawler's second Gadget.
+    Second,
+}
+
+/// Represents a Brawler's Star Power in battle.
+///
+/// The passive effects of the Star Power are defined by the `star_power_*` methods
+/// of [`BrawlerExt`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct BattleStarPower {
+    /// Name of the Star Power.
+    pub name: String,
+    /// Description of the Star Power.
+    pub description: String,
+}
+
+/// The slot of a Brawler's Star Power.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum StarPowerSlot {
+    /// The Brawler's first Star Power.
+    First,
+    /// The Brawler's second Star Power.
+    Second,
+}
+
+/// The source of the damage dealt by a player.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DamageSource {
+    /// The player's attack.
+    Attack,
+    /// The player's SUPER.
+    Ult,
+    /// The player's spawn.
+    Spawn,
+}
+
 /// Repreents a Brawler's spawn.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[non_exhaustive]
@@ -239,3 +578,10 @@ pub struct Spawn {
     /// Speed of the spawn.
     pub speed: f32,
 }
+
+impl Spawn {
+    /// Creates a new [`Spawn`].
+    pub fn new(name: String, health: u32, damage: u32, range: f32, speed: f32) -> Self {
+        Self { name, health, damage, range, speed }
+    }
+}
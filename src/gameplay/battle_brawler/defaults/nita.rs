@@ -1,8 +1,26 @@
 use async_trait::async_trait;
 
-use super::super::{BrawlerExt, BrawlerInfo};
+use super::super::{BrawlerExt, BrawlerInfo, DamageSource, GadgetSlot, StarPowerSlot};
+use crate::gameplay::player::PlayerState;
+use crate::gameplay::status::StatusEffect;
+
+/// Health recovered by Nita and her bear with her first Gadget.
+const GADGET_HEAL: u32 = 500;
+/// Percentage of damage blocked by the shield of Nita's second Gadget.
+const GADGET_SHIELD: u8 = 35;
+/// Number of turns the shield of Nita's second Gadget lasts for.
+const GADGET_SHIELD_TURNS: u8 = 2;
+/// Percentage of extra damage dealt by Nita's bear with the Hyper Bear Star Power.
+const HYPER_BEAR_BONUS: u32 = 60;
 
 /// A structure representing Nita.
+///
+/// Nita's SUPER summons the spawn from its info, Bruce, a bear which attacks the
+/// enemies on its own.
+///
+/// Nita's first Gadget heals both Nita and her bear and her second Gadget shields
+/// Nita for two turns. Her second Star Power, Hyper Bear, makes the bear attack faster,
+/// which increases the damage it deals every round. Her first Star Power has no effect.
 #[derive(Clone, Debug)]
 pub struct Nita {
     pub data: BrawlerInfo,
@@ -13,4 +31,35 @@ impl BrawlerExt for Nita {
     fn info(&self) -> &BrawlerInfo {
         &self.data
     }
+
+    fn gadget(&self, slot: GadgetSlot, player: &mut PlayerState, _brawler_level: u32) {
+        match slot {
+            GadgetSlot::First => {
+                player.heal(GADGET_HEAL);
+
+                if let Some(bear) = player.spawn.as_mut() {
+                    bear.heal(GADGET_HEAL);
+                }
+            },
+            GadgetSlot::Second => {
+                let shield = StatusEffect::Shield { percent: GADGET_SHIELD };
+                player.effects.apply(shield, GADGET_SHIELD_TURNS);
+            },
+        }
+    }
+
+    fn star_power_damage(
+        &self,
+        slot: StarPowerSlot,
+        source: DamageSource,
+        damage: u32,
+        _first: &PlayerState,
+        _second: Option<&PlayerState>,
+    ) -> u32 {
+        if slot == StarPowerSlot::Second && source == DamageSource::Spawn {
+            damage + damage * HYPER_BEAR_BONUS / 100
+        } else {
+            damage
+        }
+    }
 }
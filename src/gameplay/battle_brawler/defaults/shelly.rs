@@ -1,8 +1,17 @@
 use async_trait::async_trait;
 
-use super::super::{BrawlerExt, BrawlerInfo};
+use super::super::{BrawlerExt, BrawlerInfo, StarPowerSlot};
+use crate::gameplay::player::PlayerState;
+
+/// Percentage of the max health below which the Band-Aid Star Power is active.
+const BAND_AID_THRESHOLD: u32 = 40;
+/// Multiplier of the health recovered with the Band-Aid Star Power.
+const BAND_AID_MULTIPLIER: u32 = 3;
 
 /// A structure representing Shelly.
+///
+/// Shelly's second Star Power, Band-Aid, makes her recover health three times as fast
+/// while her health is below 40%. Her first Star Power has no effect.
 #[derive(Clone, Debug)]
 pub struct Shelly {
     pub data: BrawlerInfo,
@@ -13,4 +22,14 @@ impl BrawlerExt for Shelly {
     fn info(&self) -> &BrawlerInfo {
         &self.data
     }
+
+    fn star_power_heal(&self, slot: StarPowerSlot, amount: u32, player: &PlayerState) -> u32 {
+        if slot == StarPowerSlot::Second
+            && player.health * 100 < player.max_health * BAND_AID_THRESHOLD
+        {
+            amount * BAND_AID_MULTIPLIER
+        } else {
+            amount
+        }
+    }
 }
@@ -1,13 +1,30 @@
+pub mod bounty;
+pub mod brawlball;
 pub mod gemgrab;
+pub mod heist;
+pub mod hotzone;
 pub mod showdown;
+pub mod siege;
 
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bounty::*;
+use brawlball::*;
 use gemgrab::*;
+use heist::*;
+use hotzone::*;
 use showdown::*;
+use siege::*;
 
-use super::player::{Player, PlayerId};
-use super::{GameHandler, GameResult, Players};
-use crate::error::Result;
+use super::battle_brawler::{with_star_power, DamageSource};
+use super::player::{CharacterStatus, Player, PlayerId, Position, TeamId};
+use super::{Arena, GameEvent, GameHandler, GameResult, Players};
+use crate::error::{Error, Result};
 use crate::model::game_mode::Event;
+use crate::utils::rng::GameRng;
 
 const HEALING_TIME: u8 = 3;
 const HEALING_OVER_TIME: u32 = 100;
@@ -21,22 +38,36 @@ pub struct BattleGameMode {
 }
 
 impl BattleGameMode {
-    /// Runs the game.
-    pub async fn run(self, players: &mut Players, handler: &dyn GameHandler) -> Result<GameResult> {
-        match self.event {
-            Event::GemGrab => GemGrab::new().run(players, handler).await,
-            _ => unimplemented!(),
-        }
+    /// Creates a new [`BattleGameMode`] for the given event.
+    pub fn new(event: Event) -> Self {
+        Self { event }
+    }
+
+    /// Runs the game using the default [`GameModeRegistry`].
+    ///
+    /// Use [`GameModeRegistry::run`] to run the game with custom game mode implementations.
+    pub async fn run(
+        self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        GameModeRegistry::default().run(self.event, players, handler, rng).await
     }
 
     /// Heals a player.
     ///
     /// Whether a player is healed or not depends on the round when the player
-    /// last attacked or took damage. `true` is returned if the player is healed,
-    /// `false` if not.
+    /// last attacked or took damage. The health recovered can be modified by the
+    /// player's Star Power. `true` is returned if the player is healed, `false` if not.
     pub fn heal(player: &mut Player, round_num: u8) -> bool {
         if player.state.last_attack_round + HEALING_TIME < round_num {
-            player.heal(HEALING_OVER_TIME);
+            let brawler = &player.brawler_state.brawler;
+            let amount = with_star_power(&player.state, HEALING_OVER_TIME, |slot, amount| {
+                brawler.star_power_heal(slot, amount, &player.state)
+            });
+
+            player.heal(amount);
 
             true
         } else {
@@ -44,57 +75,425 @@ impl BattleGameMode {
         }
     }
 
-    /// Handles the stun of a player.
+    /// Starts the turn of a player.
     ///
-    /// It removes the stun if a player is stunned and informs both the players
-    /// about the stun.
+    /// It removes the player's dodge from the previous turn and ticks the status effects.
+    /// Then it revives the player if they are respawning and otherwise regenerates their
+    /// ammo and heals them.
+    ///
+    /// Returns `true` if the player can make a move in this turn, `false` if they are
+    /// respawning, dead or stunned.
     ///
     /// Forwards any error that occurs due to the event dispatch.
-    pub async fn handle_stun(
-        stunned: &mut Player,
-        other_id: &PlayerId,
+    pub async fn start_turn(
+        player_id: PlayerId,
+        players: &mut Players,
+        round_num: u8,
+        handler: &dyn GameHandler,
+    ) -> Result<bool> {
+        let player = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(false);
+        };
+
+        player.state.is_invincibile = false;
+
+        // The stun lasts for this turn even if it expires now.
+        let is_stunned = player.state.effects.is_stunned();
+        BattleGameMode::tick_effects(player_id, players, handler).await?;
+
+        let player = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(false);
+        };
+
+        if player.state.status.is_respawning() {
+            BattleGameMode::handle_respawn(player_id, players, handler).await?;
+            return Ok(false);
+        }
+
+        if player.state.is_dead() {
+            return Ok(false);
+        }
+
+        player.regenerate_ammo(round_num);
+        BattleGameMode::heal(player, round_num);
+
+        // The spawn acts on its own, even if its owner is stunned.
+        BattleGameMode::spawn_attack(player_id, players, handler).await?;
+
+        if is_stunned {
+            BattleGameMode::handle_stun(player_id, players, handler).await?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Makes the spawn of a player attack its target.
+    ///
+    /// The spawn keeps attacking the same enemy till they are defeated, after which
+    /// it goes after the next enemy who is alive. Nothing happens if the player
+    /// has no spawn or there is no enemy to attack.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn spawn_attack(
+        player_id: PlayerId,
+        players: &mut Players,
         handler: &dyn GameHandler,
     ) -> Result<()> {
-        if !stunned.state.is_stunned {
-            return Ok(());
+        let (name, damage, target) = {
+            let spawn = match players.get(player_id).and_then(|p| p.state.spawn.as_ref()) {
+                Some(spawn) => spawn,
+                None => return Ok(()),
+            };
+
+            let is_valid = |p: &Player| p.state.is_alive() && !p.state.is_respawning();
+            let target = spawn
+                .target
+                .and_then(|id| players.get(id))
+                .filter(|p| is_valid(p))
+                .or_else(|| players.enemies(player_id).find(|p| is_valid(p)))
+                .map(|p| p.id);
+
+            (spawn.info.name.clone(), spawn.info.damage, target)
+        };
+
+        let (owner, enemy) = match target.and_then(|t| players.pair_mut(player_id, t)) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+
+        if let Some(spawn) = owner.state.spawn.as_mut() {
+            spawn.target = Some(enemy.id);
         }
 
-        handler.info(&stunned.id, "You are stunned!").await?;
-        handler.info(other_id, "Opponent is stunned!").await?;
+        let brawler = &owner.brawler_state.brawler;
+        let damage = with_star_power(&owner.state, damage, |slot, damage| {
+            brawler.star_power_damage(
+                slot,
+                DamageSource::Spawn,
+                damage,
+                &owner.state,
+                Some(&enemy.state),
+            )
+        });
+
+        let health = enemy.state.health;
+        enemy.state.damage(damage);
+
+        let event = GameEvent::SpawnAttacked {
+            owner: owner.id,
+            target: enemy.id,
+            spawn: name,
+            damage: health.saturating_sub(enemy.state.health),
+        };
+        let recipients = [owner.id, enemy.id];
+
+        BattleGameMode::notify(&event, &recipients, players, handler).await
+    }
+
+    /// Makes a player attack a structure, like a Heist safe or a Siege robot, with their
+    /// attack or, if `is_ult` is `true`, their super.
+    ///
+    /// The attack goes through [`BrawlerExt::attack_structure`] or
+    /// [`BrawlerExt::ult_structure`] like the attacks on the players, so it is modified
+    /// by the player's Star Power and charges their super. Returns the damage to be
+    /// dealt to the structure, `0` if the player is not present.
+    ///
+    /// [`BrawlerExt::attack_structure`]: super::battle_brawler::BrawlerExt::attack_structure
+    /// [`BrawlerExt::ult_structure`]: super::battle_brawler::BrawlerExt::ult_structure
+    pub fn damage_structure(
+        player_id: PlayerId,
+        players: &mut Players,
+        is_ult: bool,
+        round_num: u8,
+    ) -> u32 {
+        let player = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return 0;
+        };
+
+        let brawler = &player.brawler_state.brawler;
+        let level = player.brawler_state.level;
 
-        stunned.state.is_stunned = false;
+        if is_ult {
+            brawler.ult_structure(&mut player.state, level)
+        } else {
+            player.state.last_used_ammo = round_num;
+            brawler.attack_structure(&mut player.state, level)
+        }
+    }
+
+    /// Dispatches an event to the players with given IDs.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn notify(
+        event: &GameEvent,
+        recipients: &[PlayerId],
+        players: &Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        for id in recipients {
+            if let Some(player) = players.get(*id) {
+                handler.event(player, event).await?;
+            }
+        }
 
         Ok(())
     }
 
-    /// Informs both players that the match ended in a draw because of timeout.
+    /// Dispatches an event to all the players.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn broadcast(
+        event: &GameEvent,
+        players: &Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        for player in players.iter() {
+            handler.event(player, event).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Prompts a player to choose one of the possible moves.
+    ///
+    /// `wrap` is used to convert the possible moves to [`Moves`], for example, `|moves| Moves::GemGrab(moves)`.
+    ///
+    /// Returns an error if the move index returned by the handler is out of bounds.
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn get_user_move<M: Copy + Sync>(
+        possible_moves: &[M],
+        wrap: for<'a> fn(&'a [M]) -> Moves<'a>,
+        player_id: PlayerId,
+        players: &Players,
+        handler: &dyn GameHandler,
+    ) -> Result<M> {
+        let player = players.get(player_id).ok_or(Error::NoUserFound)?;
+
+        // The enemies hidden in bushes are left out of the player's view.
+        let view = players.view(player_id);
+        let move_idx = handler.get_move_idx(wrap(possible_moves), player, &view).await?;
+        if let Some(m) = possible_moves.get(move_idx) {
+            Ok(*m)
+        } else {
+            Err(Error::ResponseError(Some(String::from("invalid move: index out of bounds."))))
+        }
+    }
+
+    /// Returns the IDs of all the players who have no health left.
+    ///
+    /// Defeated players are restored to full health when they [`respawn`](Player::respawn).
+    pub fn defeated(players: &Players) -> Vec<PlayerId> {
+        players.iter().filter(|p| p.state.health == 0).map(|p| p.id).collect()
+    }
+
+    /// Moves the player with ID `player_id` towards `to` by their
+    /// [`move_range`](Player::move_range) and informs the player about their new position.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn move_towards(
+        player_id: PlayerId,
+        to: Position,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let (from, tiles) = match players.get(player_id) {
+            Some(player) => (player.state.position, player.move_range()),
+            None => return Ok(()),
+        };
+
+        let position = players.arena().move_towards(from, to, tiles);
+        if let Some(player) = players.get_mut(player_id) {
+            player.state.position = position;
+        }
+
+        let event = GameEvent::Moved { player: player_id, position };
+        BattleGameMode::notify(&event, &[player_id], players, handler).await
+    }
+
+    /// Respawns a defeated player at the spawn point of their team and informs all the
+    /// players about it.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn respawn_defeated(
+        defeated_id: PlayerId,
+        attacker: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let spawn_point = players.spawn_point(defeated_id);
+        let defeated = if let Some(player) = players.get_mut(defeated_id) {
+            player
+        } else {
+            return Ok(());
+        };
+
+        defeated.respawn();
+
+        if let Some(position) = spawn_point {
+            defeated.state.position = position;
+        }
+
+        let event = GameEvent::Defeated { attacker, defeated: defeated_id, team: defeated.team };
+        BattleGameMode::broadcast(&event, players, handler).await
+    }
+
+    /// Handles the stun of a player.
+    ///
+    /// It informs all the players about the stun. The stun itself is removed when
+    /// it expires.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn handle_stun(
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let stunned = match players.get(player_id) {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+
+        let event = GameEvent::Stunned { player: player_id, team: stunned.team };
+        BattleGameMode::broadcast(&event, players, handler).await
+    }
+
+    /// Ticks the status effects at the start of the turn of a player.
+    ///
+    /// The player takes the damage of their poison effects and the damage over time
+    /// applied by the player is dealt to the other players. The damaged players are
+    /// informed about the damage.
     ///
     /// Forwards any error that occurs due to the event dispatch.
-    pub async fn time_out(
-        first: &PlayerId,
-        second: &PlayerId,
+    pub async fn tick_effects(
+        player_id: PlayerId,
+        players: &mut Players,
         handler: &dyn GameHandler,
     ) -> Result<()> {
-        handler.info(first, "Time's up. Match ended in a draw.").await?;
-        handler.info(second, "Time's up. Match ended in a draw.").await?;
+        let mut events = Vec::new();
+
+        for player in players.iter_mut() {
+            let damage = if player.id == player_id {
+                player.state.effects.tick()
+            } else {
+                player.state.effects.tick_damage_over_time(player_id)
+            };
+
+            if damage == 0 || player.state.is_dead() {
+                continue;
+            }
+
+            let health = player.state.health;
+            player.state.damage(damage);
+            let damage = health.saturating_sub(player.state.health);
+
+            if player.id == player_id {
+                events.push((GameEvent::PoisonTick { player: player_id, damage }, vec![player_id]));
+            } else {
+                let event = GameEvent::Damaged { attacker: player_id, target: player.id, damage };
+                events.push((event, vec![player_id, player.id]));
+            }
+        }
+
+        for (event, recipients) in events {
+            BattleGameMode::notify(&event, &recipients, players, handler).await?;
+        }
 
         Ok(())
     }
 
+    /// Informs all players that the time ran out.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn time_out(players: &Players, handler: &dyn GameHandler) -> Result<()> {
+        BattleGameMode::broadcast(&GameEvent::TimeOut, players, handler).await
+    }
+
+    /// Handles the respawn of a player.
+    ///
+    /// It informs the player that they are respawning and marks them as alive
+    /// so that they can play from their next turn.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    pub async fn handle_respawn(
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        if let Some(player) = players.get_mut(player_id) {
+            player.state.status = CharacterStatus::Alive;
+        }
+
+        BattleGameMode::dispatch_respawning_message(player_id, players, handler).await
+    }
+
     /// Informs player that they are currently respawning.
     ///
     /// Forwards any error that occurs due to the event dispatch.
     pub async fn dispatch_respawning_message(
-        player: &PlayerId,
+        player_id: PlayerId,
+        players: &Players,
         handler: &dyn GameHandler,
     ) -> Result<()> {
-        handler.info(player, "You are respawning!").await
+        let event = GameEvent::Respawning { player: player_id };
+        BattleGameMode::notify(&event, &[player_id], players, handler).await
+    }
+
+    /// Returns the result from the scores of the teams.
+    ///
+    /// The team with the highest score wins and all the other teams lose.
+    /// Returns `None` if the highest score is shared by more than one team.
+    pub fn result_from_scores(scores: &[(TeamId, u32)]) -> Option<GameResult> {
+        let highest = scores.iter().map(|(_, score)| *score).max()?;
+        let mut leaders = scores.iter().filter(|(_, score)| *score == highest);
+
+        let (winner, _) = leaders.next()?;
+        if leaders.next().is_some() {
+            return None;
+        }
+
+        let losers = scores.iter().map(|(team, _)| *team).filter(|t| t != winner).collect();
+
+        Some(GameResult::Decisive { winner: *winner, losers })
+    }
+
+    /// Returns the result of a game forfeited by the team of the player with ID `player_id`.
+    ///
+    /// The other teams keep their order in `placements`, followed by the teams missing
+    /// from it in their team order. The forfeited team is placed last unless it is
+    /// already in `placements`.
+    ///
+    /// Returns [`Error::NoUserFound`] if the player is not present.
+    pub fn forfeit(
+        player_id: PlayerId,
+        placements: &[TeamId],
+        players: &Players,
+    ) -> Result<GameResult> {
+        let forfeited = players.get(player_id).ok_or(Error::NoUserFound)?.team;
+
+        let mut placements = placements.to_vec();
+        for team in players.teams().into_iter().filter(|t| *t != forfeited) {
+            if !placements.contains(&team) {
+                placements.push(team);
+            }
+        }
+        if !placements.contains(&forfeited) {
+            placements.push(forfeited);
+        }
+
+        Ok(GameResult::Forfeit { forfeited, placements })
     }
 
     /// Returns the final result from optional result.
     ///
     /// If the optional result is `None`, the game is considered timed-out
-    /// and timeout event is dispatched for both players.
+    /// and timeout event is dispatched for all players.
     ///
     /// Forwards any error that occurs due to the event dispatch.
     pub async fn result(
@@ -105,56 +504,541 @@ impl BattleGameMode {
         Ok(if let Some(result) = result {
             result
         } else {
-            BattleGameMode::time_out(&players.0.id, &players.1.id, handler).await?;
+            BattleGameMode::time_out(players, handler).await?;
             GameResult::Draw
         })
     }
 }
 
+/// Extension trait for game modes that adds the ability to run a battle to them.
+///
+/// Implement this trait for custom game modes and register them in a [`GameModeRegistry`]
+/// to override the default behaviour of an [`Event`].
+#[async_trait]
+pub trait GameModeExt: Send + Sync + Debug {
+    /// Runs the game mode with given players until the game ends.
+    ///
+    /// All the randomness in the game must be drawn from `rng` so that the game
+    /// can be reproduced from the seed of the generator.
+    async fn run(
+        &self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<GameResult>;
+}
+
+/// Implements [`GameModeExt`] for built-in game modes by running a copy of the mode.
+macro_rules! impl_game_mode_ext {
+    ($($mode:ident),* $(,)?) => {
+        $(
+            #[async_trait]
+            impl GameModeExt for $mode {
+                async fn run(
+                    &self,
+                    players: &mut Players,
+                    handler: &dyn GameHandler,
+                    rng: &mut GameRng,
+                ) -> Result<GameResult> {
+                    $mode::run(Clone::clone(self), players, handler, rng).await
+                }
+            }
+        )*
+    };
+}
+
+impl_game_mode_ext!(GemGrab, BrawlBall, Heist, Bounty, HotZone, Showdown, Siege);
+
+/// A mapping of events and the game mode implementations used to run them.
+///
+/// The default registry contains the built-in implementations of all supported events.
+#[derive(Clone, Debug)]
+pub struct GameModeRegistry(HashMap<Event, Arc<dyn GameModeExt>>);
+
+impl GameModeRegistry {
+    /// Creates a new, empty [`GameModeRegistry`].
+    ///
+    /// Use [`GameModeRegistry::default`] to create a registry with the built-in game modes.
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Registers a game mode implementation for an event.
+    ///
+    /// Returns the previously registered implementation for the event, if any.
+    pub fn register<M: 'static + GameModeExt>(
+        &mut self,
+        event: Event,
+        mode: M,
+    ) -> Option<Arc<dyn GameModeExt>> {
+        self.0.insert(event, Arc::new(mode))
+    }
+
+    /// Removes the game mode implementation registered for an event.
+    pub fn unregister(&mut self, event: Event) -> Option<Arc<dyn GameModeExt>> {
+        self.0.remove(&event)
+    }
+
+    /// Returns the game mode implementation registered for an event.
+    pub fn get(&self, event: Event) -> Option<&dyn GameModeExt> {
+        self.0.get(&event).map(AsRef::as_ref)
+    }
+
+    /// Checks if a game mode implementation is registered for an event.
+    pub fn contains(&self, event: Event) -> bool {
+        self.0.contains_key(&event)
+    }
+
+    /// Runs the game mode registered for `event`.
+    ///
+    /// A game stopped by [`Error::Forfeit`] or [`Error::AfkForfeit`] ends with a
+    /// [`GameResult::Forfeit`]. All the players are informed about the result once
+    /// the game ends.
+    ///
+    /// Returns [`Error::UnregisteredGameMode`] if no implementation is registered for the event.
+    pub async fn run(
+        &self,
+        event: Event,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        let mode = self.get(event).ok_or(Error::UnregisteredGameMode(event))?;
+        let result = match mode.run(players, handler, rng).await {
+            Ok(result) => result,
+            Err(Error::Forfeit(id) | Error::AfkForfeit(id)) => {
+                BattleGameMode::forfeit(id, &[], players)?
+            },
+            Err(e) => return Err(e),
+        };
+
+        let event = GameEvent::GameOver(result.clone());
+        BattleGameMode::broadcast(&event, players, handler).await?;
+
+        Ok(result)
+    }
+}
+
+impl Default for GameModeRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Event::GemGrab, GemGrab::new());
+        registry.register(Event::Showdown, Showdown::new());
+        registry.register(Event::BrawlBall, BrawlBall::new());
+        registry.register(Event::Heist, Heist::new());
+        registry.register(Event::Bounty, Bounty::new());
+        registry.register(Event::Siege, Siege::new());
+        registry.register(Event::HotZone, HotZone::new());
+
+        registry
+    }
+}
+
 /// Represents a valid move in a game mode.
 #[derive(Clone, Copy, Debug)]
 #[non_exhaustive]
 pub enum Moves<'a> {
     /// Moves in Gem Grab.
     GemGrab(&'a [GemGrabMove]),
+    /// Moves in Showdown.
     Showdown(&'a [ShowdownMove]),
+    /// Moves in Brawl Ball.
+    BrawlBall(&'a [BrawlBallMove]),
+    /// Moves in Heist.
+    Heist(&'a [HeistMove]),
+    /// Moves in Bounty.
+    Bounty(&'a [BountyMove]),
+    /// Moves in Siege.
+    Siege(&'a [SiegeMove]),
+    /// Moves in Hot Zone.
+    HotZone(&'a [HotZoneMove]),
+}
+
+impl Moves<'_> {
+    /// Returns the number of possible moves.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::GemGrab(moves) => moves.len(),
+            Self::Showdown(moves) => moves.len(),
+            Self::BrawlBall(moves) => moves.len(),
+            Self::Heist(moves) => moves.len(),
+            Self::Bounty(moves) => moves.len(),
+            Self::Siege(moves) => moves.len(),
+            Self::HotZone(moves) => moves.len(),
+        }
+    }
+
+    /// Checks if there are no possible moves.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 /// Represents a user move.
+///
+/// Moves which act on another player carry the ID of their target.
 #[derive(Copy, Clone, Debug)]
 #[non_exhaustive]
 pub enum GeneralMove {
     /// Attack enemy Brawler.
-    Attack,
+    Attack(PlayerId),
     /// Use super. It can either spawn a character or attack enemy Brawler.
-    Ult,
+    Ult(PlayerId),
     /// Attack enemy spawn.
-    AttackSpawn,
+    AttackSpawn(PlayerId),
     /// Use super attack on enemy spawn.
-    UltSpawn,
-    /// Dodge the next move.
+    UltSpawn(PlayerId),
+    /// Use the equipped Gadget.
+    UseGadget,
+    /// Move towards enemy Brawler.
+    MoveTowards(PlayerId),
+    /// Move away from enemy Brawler.
+    Retreat(PlayerId),
+    /// Move sideways, keeping the same direction to enemy Brawler.
+    Strafe(PlayerId),
+    /// Dodge all the damage till the next turn.
     Dodge,
-    /* /// Shoot the ball using a normal attack.
-     * ///
-     * /// It is valid in Brawl Ball only.
-     * ShootBall,
-     * /// Shoot the ball using the super.
-     * ///
-     * /// It is valid in Brawl Ball only.
-     * UltShootBall, */
+    /// Surrender and forfeit the game for the team.
+    Surrender,
 }
 
 impl GeneralMove {
-    /// Handles a general move.
-    pub async fn handle_move(&self, first: &mut Player, second: &mut Player) {
-        let brawler = &first.brawler_state.brawler;
-        let brawler_level = first.brawler_state.level;
+    /// Returns the ID of the player targeted by the move, if any.
+    pub fn target(&self) -> Option<PlayerId> {
         match self {
-            Self::Attack => brawler.attack(&mut first.state, &mut second.state, brawler_level),
-            Self::Ult => brawler.ult(&mut first.state, &mut second.state, brawler_level),
-            Self::AttackSpawn => unimplemented!(),
-            Self::UltSpawn => unimplemented!(),
-            Self::Dodge => first.state.is_invincibile = true,
+            Self::Attack(target)
+            | Self::Ult(target)
+            | Self::AttackSpawn(target)
+            | Self::UltSpawn(target)
+            | Self::MoveTowards(target)
+            | Self::Retreat(target)
+            | Self::Strafe(target) => Some(*target),
+            Self::UseGadget | Self::Dodge | Self::Surrender => None,
         }
     }
+
+    /// Checks if the move changes the position of the player.
+    pub fn is_movement(&self) -> bool {
+        matches!(self, Self::MoveTowards(_) | Self::Retreat(_) | Self::Strafe(_))
+    }
+
+    /// Returns all the general moves a player can make against their enemies.
+    ///
+    /// A player can always dodge or surrender and can use their Gadget while it has
+    /// charges left. A player can move relative to their enemies unless they are slowed
+    /// completely. The enemies hidden from the player can't be targeted, and the enemies
+    /// out of the player's line of sight and their spawns can't be attacked.
+    pub fn possible_moves(player: &Player, players: &Players) -> Vec<Self> {
+        let mut moves = vec![Self::Dodge, Self::Surrender];
+
+        if player.can_use_gadget() {
+            moves.push(Self::UseGadget);
+        }
+
+        let can_attack = player.can_attack();
+        let can_super = player.can_super();
+        let can_move = player.move_range() > 0;
+
+        let arena = players.arena();
+        let is_spawner = player.brawler_state.brawler.has_spawn();
+
+        for enemy in players.enemies(player.id).filter(|e| players.is_visible(player.id, e.id)) {
+            // A spawn has no position of its own and is targeted where its owner stands.
+            let in_sight = arena.has_line_of_sight(&player.state.position, &enemy.state.position);
+
+            if enemy.state.is_alive() && !enemy.state.is_respawning() {
+                if in_sight {
+                    if can_attack {
+                        moves.push(Self::Attack(enemy.id));
+                    }
+
+                    if can_super {
+                        moves.push(Self::Ult(enemy.id));
+                    }
+                }
+
+                if can_move {
+                    moves.extend([
+                        Self::MoveTowards(enemy.id),
+                        Self::Retreat(enemy.id),
+                        Self::Strafe(enemy.id),
+                    ]);
+                }
+            }
+
+            if enemy.state.spawn.is_some() && in_sight {
+                if can_attack {
+                    moves.push(Self::AttackSpawn(enemy.id));
+                }
+
+                // The super of a spawner summons its own spawn instead.
+                if can_super && !is_spawner {
+                    moves.push(Self::UltSpawn(enemy.id));
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Handles a general move made by the player with ID `player_id`.
+    ///
+    /// The attacker and the target are informed about the damage dealt by the move.
+    /// All the players are informed when a Gadget is used and the player is informed
+    /// about their new position when they move.
+    ///
+    /// Returns [`Error::Forfeit`] if the player surrenders, which ends the game with
+    /// a forfeit. Forwards any error that occurs due to the event dispatch.
+    pub async fn handle_move(
+        &self,
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        match *self {
+            Self::Attack(target) => Self::attack(player_id, target, false, players, handler).await,
+            Self::Ult(target) => Self::attack(player_id, target, true, players, handler).await,
+            Self::AttackSpawn(target) => {
+                Self::attack_spawn(player_id, target, false, players, handler).await
+            },
+            Self::UltSpawn(target) => {
+                Self::attack_spawn(player_id, target, true, players, handler).await
+            },
+            Self::UseGadget => Self::use_gadget(player_id, players, handler).await,
+            Self::MoveTowards(target) => {
+                Self::move_player(player_id, target, Arena::move_towards, players, handler).await
+            },
+            Self::Retreat(target) => {
+                Self::move_player(player_id, target, Arena::retreat, players, handler).await
+            },
+            Self::Strafe(target) => {
+                Self::move_player(player_id, target, Arena::strafe, players, handler).await
+            },
+            Self::Dodge => {
+                if let Some(player) = players.get_mut(player_id) {
+                    player.state.is_invincibile = true;
+                }
+
+                Ok(())
+            },
+            Self::Surrender => Err(Error::Forfeit(player_id)),
+        }
+    }
+
+    /// Makes the player with ID `player_id` attack or use their super on the player
+    /// with ID `target`.
+    ///
+    /// The attacker and the target are informed about the damage dealt. All the players
+    /// are informed if the super summons a spawn instead, which goes after the target.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn attack(
+        player_id: PlayerId,
+        target: PlayerId,
+        is_ult: bool,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let (first, second) = match players.pair_mut(player_id, target) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+
+        let health = second.state.health;
+        let brawler = &first.brawler_state.brawler;
+        let level = first.brawler_state.level;
+
+        if !is_ult {
+            brawler.attack(&mut first.state, &mut second.state, level);
+        } else {
+            brawler.ult(&mut first.state, &mut second.state, level);
+
+            if let Some(spawn) = first.state.spawn.as_mut() {
+                spawn.target = Some(target);
+
+                let event = GameEvent::SpawnSummoned {
+                    owner: player_id,
+                    team: first.team,
+                    spawn: spawn.info.name.clone(),
+                };
+                return BattleGameMode::broadcast(&event, players, handler).await;
+            }
+        }
+
+        let damage = health.saturating_sub(second.state.health);
+        let event = GameEvent::Damaged { attacker: player_id, target, damage };
+        BattleGameMode::notify(&event, &[player_id, target], players, handler).await
+    }
+
+    /// Makes the player with ID `player_id` attack or use their super on the spawn of
+    /// the player with ID `owner`.
+    ///
+    /// The attacker and the owner are informed about the damage dealt to the spawn.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn attack_spawn(
+        player_id: PlayerId,
+        owner: PlayerId,
+        is_ult: bool,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let (first, second) = match players.pair_mut(player_id, owner) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+
+        let spawn_health = |player: &Player| player.state.spawn.as_ref().map_or(0, |s| s.health);
+        let health = spawn_health(second);
+        let brawler = &first.brawler_state.brawler;
+        let level = first.brawler_state.level;
+
+        if !is_ult {
+            brawler.attack_spawn(&mut first.state, &mut second.state, level);
+        } else {
+            brawler.ult_spawn(&mut first.state, &mut second.state, level);
+        }
+
+        let damage = health.saturating_sub(spawn_health(second));
+        let event = GameEvent::SpawnDamaged { attacker: player_id, owner, damage };
+        BattleGameMode::notify(&event, &[player_id, owner], players, handler).await
+    }
+
+    /// Makes the player with ID `player_id` use their Gadget and informs all the players
+    /// about it.
+    ///
+    /// Nothing happens if the player has no Gadget equipped or no charges left.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn use_gadget(
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let player = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(());
+        };
+
+        let (slot, gadget) = match (player.brawler_state.gadget, player.brawler_state.gadget()) {
+            (Some(slot), Some(gadget)) if player.can_use_gadget() => (slot, gadget.name.clone()),
+            _ => return Ok(()),
+        };
+
+        player.state.gadget_charges -= 1;
+        let level = player.brawler_state.level;
+        player.brawler_state.brawler.gadget(slot, &mut player.state, level);
+
+        let event = GameEvent::GadgetUsed { player: player_id, team: player.team, gadget };
+        BattleGameMode::broadcast(&event, players, handler).await
+    }
+
+    /// Moves the player with ID `player_id` relative to the player with ID `target`.
+    ///
+    /// The player moves as many tiles as their [`move_range`](Player::move_range) in
+    /// the way given by `step`, staying inside the arena, and is informed about their
+    /// new position.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn move_player(
+        player_id: PlayerId,
+        target: PlayerId,
+        step: fn(&Arena, Position, Position, u32) -> Position,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let (player, target) = match (players.get(player_id), players.get(target)) {
+            (Some(player), Some(target)) => (player, target),
+            _ => return Ok(()),
+        };
+
+        let position = step(
+            players.arena(),
+            player.state.position,
+            target.state.position,
+            player.move_range(),
+        );
+
+        if let Some(player) = players.get_mut(player_id) {
+            player.state.position = position;
+        }
+
+        let event = GameEvent::Moved { player: player_id, position };
+        BattleGameMode::notify(&event, &[player_id], players, handler).await
+    }
+}
+
+#[cfg(test)]
+mod test_battle_game_mode {
+    use super::*;
+    use crate::gameplay::ai::{AiHandler, RandomStrategy};
+    use crate::gameplay::battle_brawler::defaults::Nita;
+    use crate::gameplay::battle_brawler::{BattleGadget, GadgetSlot, Spawn};
+    use crate::gameplay::player::{PlayerBrawlerState, PlayerSpawn};
+    use crate::gameplay::test_utils::{block_on, nita_info, players, shelly};
+    use crate::gameplay::Arena;
+    use crate::model::map::Map;
+    use crate::resource::bs_box::UnlockedGadgets;
+
+    #[test]
+    fn test_line_of_sight() {
+        let mut players = players();
+        players.get_mut(PlayerId(1)).unwrap().state.attacks = 4;
+        let spawn = Spawn::new(String::from("Bruce"), 4000, 500, 2.0, 720.0);
+        players.get_mut(PlayerId(4)).unwrap().state.spawn = Some(PlayerSpawn::new(spawn));
+
+        let moves = |players: &Players| {
+            GeneralMove::possible_moves(players.get(PlayerId(1)).unwrap(), players)
+        };
+        let is_targeted = |m: &GeneralMove| m.target().is_some() && !m.is_movement();
+
+        let targeted: Vec<GeneralMove> = moves(&players).into_iter().filter(is_targeted).collect();
+        assert!(matches!(targeted[..4], [
+            GeneralMove::Attack(PlayerId(4)),
+            GeneralMove::Ult(PlayerId(4)),
+            GeneralMove::AttackSpawn(PlayerId(4)),
+            GeneralMove::UltSpawn(PlayerId(4))
+        ]));
+
+        // The enemies behind the wall can only be moved against.
+        let map = Map::from_ascii("Wall", ".....\n..1..\n#####\n..0..\n.....").unwrap();
+        players.set_arena(Arena::from_map(map));
+        assert!(!moves(&players).iter().any(is_targeted));
+        let move_towards = |m: &GeneralMove| matches!(m, GeneralMove::MoveTowards(PlayerId(4)));
+        assert!(moves(&players).iter().any(move_towards));
+    }
+
+    #[test]
+    fn test_use_gadget() {
+        let mut data = nita_info();
+        data.gadget1 = Some(BattleGadget {
+            name: String::from("Bear Hug"),
+            description: String::from("Heals Nita and Bruce."),
+            charges: 1,
+        });
+
+        let mut brawler = PlayerBrawlerState::new(Nita { data }, 9);
+        let mut unlocked = UnlockedGadgets::default();
+        unlocked.set_first(true);
+        brawler.equip_gadget(GadgetSlot::First, unlocked);
+
+        let nita = Player::new(PlayerId(1), brawler, true);
+        let mut players = Players::from_teams(vec![vec![nita], vec![shelly(4, false)]]);
+
+        // The Gadget is offered once no matter how many enemies there are.
+        let moves = |players: &Players| {
+            GeneralMove::possible_moves(players.get(PlayerId(1)).unwrap(), players)
+        };
+        let is_gadget = |m: &&GeneralMove| matches!(m, GeneralMove::UseGadget);
+        assert_eq!(moves(&players).iter().filter(is_gadget).count(), 1);
+
+        players.get_mut(PlayerId(1)).unwrap().state.damage(1000);
+        let handler = AiHandler::new(RandomStrategy, 7);
+        block_on(GeneralMove::UseGadget.handle_move(PlayerId(1), &mut players, &handler)).unwrap();
+
+        let nita = players.get(PlayerId(1)).unwrap();
+        assert_eq!((nita.state.health, nita.state.gadget_charges), (3300, 0));
+        assert_eq!(moves(&players).iter().filter(is_gadget).count(), 0);
+    }
 }
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BattleGameMode, GeneralMove, Moves};
+use crate::error::Result;
+use crate::gameplay::player::{PlayerId, PlayerState, TeamId};
+use crate::gameplay::{GameEvent, GameHandler, GameResult, Players};
+use crate::utils::rng::GameRng;
+
+/// The bounty every player starts with.
+const STARTING_BOUNTY: u32 = 2;
+/// The maximum bounty a player can have.
+const MAX_BOUNTY: u32 = 7;
+/// The number of stars awarded for collecting the center star.
+const CENTER_STAR_VALUE: u32 = 1;
+/// The number of rounds after which the game ends.
+const MAX_ROUNDS: u8 = 75;
+
+/// Represents Bounty.
+///
+/// Bounty is a team gamemode (3v3 in-game) where you earn stars by defeating enemies.
+/// Every player has a bounty on their head which starts at 2 stars and increases by one
+/// star with every kill, up to 7 stars. Defeating a player awards their bounty to the
+/// attacker and resets the bounty of the defeated player.
+///
+/// A single star is available at the center of the map at the start of the game.
+/// The team with more stars wins when the time runs out.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct Bounty {
+    /// The round number of the game.
+    round_num: u8,
+    /// Whether the center star is still available or not.
+    center_star: bool,
+}
+
+impl Default for Bounty {
+    fn default() -> Self {
+        Self { round_num: 0, center_star: true }
+    }
+}
+
+impl Bounty {
+    /// Creates a new [`Bounty`] object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn initialize_player(&self, player: &mut PlayerState) {
+        player.mode_state = BountyState::new().into();
+    }
+
+    /// Runs Bounty.
+    pub async fn run(
+        mut self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        _rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        for player in players.iter_mut() {
+            self.initialize_player(&mut player.state);
+        }
+
+        let turn_order = players.turn_order();
+
+        while self.round_num < MAX_ROUNDS {
+            for &player_id in &turn_order {
+                if BattleGameMode::start_turn(player_id, players, self.round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::Bounty(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
+
+                    self.handle_move(&user_move, player_id, players, handler).await?;
+                }
+
+                for defeated_id in BattleGameMode::defeated(players) {
+                    // Players defeated by their own poison don't award any stars.
+                    let event =
+                        players.pair_mut(player_id, defeated_id).map(|(attacker, defeated)| {
+                            let stars = self.claim_bounty(&mut attacker.state, &mut defeated.state);
+                            GameEvent::StarsEarned { player: player_id, team: attacker.team, stars }
+                        });
+
+                    BattleGameMode::respawn_defeated(defeated_id, player_id, players, handler)
+                        .await?;
+
+                    if let Some(event) = event {
+                        BattleGameMode::broadcast(&event, players, handler).await?;
+                    }
+                }
+            }
+
+            self.round_num += 1;
+        }
+
+        let result = self.check_result(players);
+        BattleGameMode::result(result, players, handler).await
+    }
+
+    /// Awards the bounty of the defeated player to the attacker.
+    ///
+    /// The bounty of the attacker is increased by one star and the bounty of the
+    /// defeated player is reset. Returns the number of stars awarded.
+    fn claim_bounty(&self, attacker: &mut PlayerState, defeated: &mut PlayerState) -> u32 {
+        let defeated = defeated.mode_or_default::<BountyState>();
+        let bounty = std::mem::replace(&mut defeated.bounty, STARTING_BOUNTY);
+
+        let attacker = attacker.mode_or_default::<BountyState>();
+        attacker.stars = attacker.stars.saturating_add(bounty);
+        attacker.bounty = MAX_BOUNTY.min(attacker.bounty + 1);
+
+        bounty
+    }
+
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<BountyMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
+
+        let mut moves: Vec<BountyMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(BountyMove::General)
+            .collect();
+
+        if self.center_star {
+            moves.push(BountyMove::CollectCenterStar);
+        }
+
+        moves
+    }
+
+    /// Returns the total number of stars earned by each team.
+    fn team_stars(&self, players: &Players) -> Vec<(TeamId, u32)> {
+        players
+            .teams()
+            .into_iter()
+            .map(|team| {
+                let stars = players
+                    .team(team)
+                    .map(|p| p.state.mode::<BountyState>().map_or(0, |state| state.stars))
+                    .sum();
+                (team, stars)
+            })
+            .collect()
+    }
+
+    /// Returns the result based on the stars of the teams.
+    ///
+    /// Returns `None` if the highest number of stars is shared by more than one team.
+    fn check_result(&self, players: &Players) -> Option<GameResult> {
+        BattleGameMode::result_from_scores(&self.team_stars(players))
+    }
+
+    async fn handle_move(
+        &mut self,
+        user_move: &BountyMove,
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        match user_move {
+            BountyMove::General(gm) => gm.handle_move(player_id, players, handler).await,
+            BountyMove::CollectCenterStar => {
+                let first = if let Some(player) = players.get_mut(player_id) {
+                    player
+                } else {
+                    return Ok(());
+                };
+
+                first.state.mode_or_default::<BountyState>().stars += CENTER_STAR_VALUE;
+
+                self.center_star = false;
+
+                let event = GameEvent::StarsEarned {
+                    player: player_id,
+                    team: first.team,
+                    stars: CENTER_STAR_VALUE,
+                };
+                BattleGameMode::broadcast(&event, players, handler).await
+            },
+        }
+    }
+}
+
+/// Represents the state of a player in Bounty.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct BountyState {
+    /// The number of stars the player has earned for their team.
+    pub stars: u32,
+    /// The number of stars awarded for defeating the player.
+    pub bounty: u32,
+}
+
+impl BountyState {
+    /// Creates a new [`BountyState`] with no stars and the starting bounty.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for BountyState {
+    fn default() -> Self {
+        Self { stars: 0, bounty: STARTING_BOUNTY }
+    }
+}
+
+/// Represents a user move in Bounty.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum BountyMove {
+    /// Represents a general move.
+    General(GeneralMove),
+    /// Represents the move to collect the star at the center of the map.
+    CollectCenterStar,
+}
+
+#[cfg(test)]
+mod test_bounty {
+    use super::*;
+
+    #[test]
+    fn test_claim_bounty() {
+        let bounty = Bounty::new();
+        let (mut attacker, mut defeated) = (PlayerState::new(3, 3600), PlayerState::new(3, 3600));
+        defeated.mode_state = BountyState { stars: 0, bounty: 5 }.into();
+
+        assert_eq!(bounty.claim_bounty(&mut attacker, &mut defeated), 5);
+        assert_eq!(attacker.mode(), Some(&BountyState { stars: 5, bounty: 3 }));
+        assert_eq!(defeated.mode(), Some(&BountyState { stars: 0, bounty: STARTING_BOUNTY }));
+
+        // The bounty of the attacker stops growing at the maximum bounty.
+        for _ in 0..10 {
+            bounty.claim_bounty(&mut attacker, &mut defeated);
+        }
+        assert_eq!(attacker.mode::<BountyState>().map(|s| s.bounty), Some(MAX_BOUNTY));
+        assert_eq!(attacker.mode::<BountyState>().map(|s| s.stars), Some(25));
+    }
+}
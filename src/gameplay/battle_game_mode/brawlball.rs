@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{BattleGameMode, GeneralMove, Moves};
+use crate::error::Result;
+use crate::gameplay::player::{Player, PlayerId, PlayerState, Position, TeamId};
+use crate::gameplay::{GameEvent, GameHandler, GameResult, Players};
+use crate::model::map::Tile;
+use crate::utils::rng::{self, GameRng};
+
+/// The number of goals required to win the game.
+const GOALS_TO_WIN: u32 = 2;
+/// The number of tiles the ball travels when it is passed or a shot misses.
+const PASS_DISTANCE: u32 = 2;
+/// The number of tiles from which a player can pick up the loose ball.
+const PICK_UP_RANGE: u32 = 1;
+/// The round number at which the regular time ends.
+const REGULAR_TIME_ROUNDS: u8 = 75;
+/// The number of rounds played in overtime.
+const OVERTIME_ROUNDS: u8 = 15;
+
+/// Represents Brawl Ball.
+///
+/// Brawl Ball is a team gamemode (3v3 in-game) where your team needs to score 2 goals
+/// before the enemy team to win. The ball starts at the center of the arena and can be
+/// dribbled, passed to a teammate or shot at the enemy goal once it is in range. Each
+/// team defends the goal nearest to its spawn point.
+///
+/// If the ball carrier is defeated, the ball is dropped where they died. If the
+/// scores are tied when the time runs out, the game goes into overtime where
+/// the goals are easier to score and the next goal wins.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct BrawlBall {
+    /// The round number of the game.
+    round_num: u8,
+    /// The player who has the ball, if any.
+    carrier: Option<PlayerId>,
+    /// Position of the loose ball, which moves with the carrier while the ball is carried.
+    ball: Position,
+    /// The goals defended by the teams.
+    goals: HashMap<TeamId, Position>,
+}
+
+impl Default for BrawlBall {
+    fn default() -> Self {
+        Self { round_num: 0, carrier: None, ball: Position::new(0, 0), goals: HashMap::new() }
+    }
+}
+
+impl BrawlBall {
+    /// Creates a new [`BrawlBall`] object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn initialize_player(&self, player: &mut PlayerState) {
+        player.mode_state = BrawlBallState::new().into();
+    }
+
+    /// Runs Brawl Ball.
+    pub async fn run(
+        mut self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        for player in players.iter_mut() {
+            self.initialize_player(&mut player.state);
+        }
+
+        let arena = players.arena();
+        self.ball = arena.center();
+
+        for team in players.teams() {
+            if let Some(spawn) = players.team_spawn(team) {
+                self.goals.insert(team, arena.structure(Tile::Goal, spawn));
+            }
+        }
+
+        let turn_order = players.turn_order();
+        let mut result = None;
+
+        'game: while self.round_num < REGULAR_TIME_ROUNDS + OVERTIME_ROUNDS {
+            if self.round_num == REGULAR_TIME_ROUNDS {
+                if let Some(res) = self.check_time_up(players) {
+                    result = Some(res);
+                    break;
+                }
+
+                BattleGameMode::broadcast(&GameEvent::Overtime, players, handler).await?;
+            }
+
+            for &player_id in &turn_order {
+                if BattleGameMode::start_turn(player_id, players, self.round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::BrawlBall(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
+
+                    self.handle_move(&user_move, player_id, players, handler, rng).await?;
+                }
+
+                for defeated_id in BattleGameMode::defeated(players) {
+                    if self.carrier == Some(defeated_id) {
+                        self.ball = self.ball(players);
+                        self.carrier = None;
+                    }
+
+                    BattleGameMode::respawn_defeated(defeated_id, player_id, players, handler)
+                        .await?;
+                }
+
+                if let Some(res) = self.check_result(players) {
+                    result = Some(res);
+                    break 'game;
+                }
+            }
+
+            self.round_num += 1;
+        }
+
+        BattleGameMode::result(result, players, handler).await
+    }
+
+    /// Returns the position of the ball, which is where the carrier stands if the ball
+    /// is carried.
+    fn ball(&self, players: &Players) -> Position {
+        self.carrier.and_then(|id| players.get(id)).map_or(self.ball, |p| p.state.position)
+    }
+
+    /// Returns the position of the goal the team of the player attacks, which is the
+    /// goal of the first enemy team.
+    fn enemy_goal(&self, player: &Player) -> Option<Position> {
+        self.goals
+            .iter()
+            .filter(|(team, _)| **team != player.team)
+            .min_by_key(|(team, _)| **team)
+            .map(|(_, goal)| *goal)
+    }
+
+    /// Checks if the enemy goal is within the range of the player's normal attack or
+    /// super.
+    fn can_shoot(&self, player: &Player, is_ult: bool) -> bool {
+        self.enemy_goal(player).is_some_and(|goal| player.in_range(&goal, is_ult))
+    }
+
+    /// Checks if the game is in overtime.
+    fn is_overtime(&self) -> bool {
+        self.round_num >= REGULAR_TIME_ROUNDS
+    }
+
+    /// Returns the weights of missing and scoring a shot from given number of tiles.
+    fn shot_weights(&self, distance: u32, is_ult: bool) -> [u32; 2] {
+        // The goals are easier to score in overtime because the walls are destroyed.
+        let distance = if self.is_overtime() { distance.div_ceil(2) } else { distance };
+
+        match (distance, is_ult) {
+            (0..=2, _) => [0, 1],
+            (3..=4, true) => [0, 1],
+            (3..=4, false) => [1, 1],
+            (5..=6, true) => [1, 1],
+            (_, true) => [2, 1],
+            (_, false) => [3, 1],
+        }
+    }
+
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<BrawlBallMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
+
+        if self.carrier == Some(player_id) {
+            // The ball carrier can't attack, but can use its attack to shoot the ball.
+            let mut moves = vec![
+                BrawlBallMove::General(GeneralMove::Dodge),
+                BrawlBallMove::General(GeneralMove::Surrender),
+                BrawlBallMove::Dribble,
+                BrawlBallMove::PassBall,
+            ];
+
+            if player.can_attack() && self.can_shoot(player, false) {
+                moves.push(BrawlBallMove::ShootBall);
+            }
+
+            if player.can_super() && self.can_shoot(player, true) {
+                moves.push(BrawlBallMove::UltShootBall);
+            }
+
+            return moves;
+        }
+
+        let mut moves: Vec<BrawlBallMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(BrawlBallMove::General)
+            .collect();
+
+        if self.carrier.is_none() {
+            if player.state.position.tiles_from(&self.ball) <= PICK_UP_RANGE {
+                moves.push(BrawlBallMove::PickUpBall);
+            } else if player.move_range() > 0 {
+                moves.push(BrawlBallMove::MoveToBall);
+            }
+        }
+
+        moves
+    }
+
+    /// Returns the total number of goals scored by each team.
+    fn team_goals(&self, players: &Players) -> Vec<(TeamId, u32)> {
+        players
+            .teams()
+            .into_iter()
+            .map(|team| {
+                let goals = players
+                    .team(team)
+                    .map(|p| p.state.mode::<BrawlBallState>().map_or(0, |state| state.goals))
+                    .sum();
+                (team, goals)
+            })
+            .collect()
+    }
+
+    /// Returns the result if the time is up and the scores are not tied.
+    fn check_time_up(&self, players: &Players) -> Option<GameResult> {
+        BattleGameMode::result_from_scores(&self.team_goals(players))
+    }
+
+    fn check_result(&self, players: &Players) -> Option<GameResult> {
+        if self.is_overtime() {
+            // The next goal wins in overtime.
+            return self.check_time_up(players);
+        }
+
+        let team_goals = self.team_goals(players);
+        if team_goals.iter().any(|(_, goals)| *goals >= GOALS_TO_WIN) {
+            BattleGameMode::result_from_scores(&team_goals)
+        } else {
+            None
+        }
+    }
+
+    /// Handles the move of a user.
+    ///
+    /// All the players are informed if a goal is scored by the move.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn handle_move(
+        &mut self,
+        user_move: &BrawlBallMove,
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<()> {
+        let goal = match players.get(player_id) {
+            Some(player) => self.enemy_goal(player).unwrap_or(self.ball),
+            None => return Ok(()),
+        };
+
+        match user_move {
+            BrawlBallMove::General(gm) => return gm.handle_move(player_id, players, handler).await,
+            BrawlBallMove::MoveToBall => {
+                return BattleGameMode::move_towards(player_id, self.ball, players, handler).await;
+            },
+            // The ball moves with the carrier.
+            BrawlBallMove::Dribble => {
+                return BattleGameMode::move_towards(player_id, goal, players, handler).await;
+            },
+            _ => {},
+        }
+
+        // The ball is passed to the first teammate who can receive it, if any.
+        let receiver = players
+            .teammates(player_id)
+            .filter(|p| p.state.is_alive() && !p.state.is_respawning())
+            .map(|p| p.id)
+            .next();
+
+        let arena = players.arena().clone();
+        let first = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(());
+        };
+
+        let mut scored = false;
+
+        match user_move {
+            BrawlBallMove::General(_) | BrawlBallMove::MoveToBall | BrawlBallMove::Dribble => {},
+            BrawlBallMove::PickUpBall => {
+                // 75% chance of picking up the ball.
+                if *rng::select_one(&[false, true], &[1, 3], rng).unwrap_or(&false) {
+                    self.carrier = Some(first.id);
+                }
+            },
+            BrawlBallMove::PassBall => {
+                // The ball is left loose if there is no teammate to receive it.
+                self.ball = arena.move_towards(first.state.position, goal, PASS_DISTANCE);
+                self.carrier = receiver;
+            },
+            BrawlBallMove::ShootBall | BrawlBallMove::UltShootBall => {
+                let is_ult = matches!(user_move, BrawlBallMove::UltShootBall);
+                if is_ult {
+                    first.state.attacks = 0;
+                } else {
+                    first.state.ammo -= 1;
+                    first.state.last_used_ammo = self.round_num;
+                }
+
+                let distance = first.state.position.tiles_from(&goal);
+                let weights = self.shot_weights(distance, is_ult);
+                scored = *rng::select_one(&[false, true], &weights, rng).unwrap_or(&false);
+
+                if scored {
+                    first.state.mode_or_default::<BrawlBallState>().goals += 1;
+
+                    self.ball = arena.center();
+                } else {
+                    // A missed shot still travels towards the goal.
+                    self.ball = arena.move_towards(first.state.position, goal, PASS_DISTANCE);
+                }
+
+                self.carrier = None;
+            },
+        }
+
+        if scored {
+            let event = GameEvent::Goal { scorer: player_id, team: first.team };
+            BattleGameMode::broadcast(&event, players, handler).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents the state of a player in Brawl Ball.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct BrawlBallState {
+    /// The number of goals the player has scored for their team.
+    pub goals: u32,
+}
+
+impl BrawlBallState {
+    /// Creates a new [`BrawlBallState`] with no goals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Represents a user move in Brawl Ball.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum BrawlBallMove {
+    /// Represents a general move.
+    General(GeneralMove),
+    /// Represents the move towards the loose ball.
+    MoveToBall,
+    /// Represents the move to pick up the loose ball, once the player is next to it.
+    PickUpBall,
+    /// Represents the move to carry the ball towards the enemy goal.
+    Dribble,
+    /// Represents the move to pass the ball to a teammate.
+    ///
+    /// The ball is left loose if no teammate can receive it.
+    PassBall,
+    /// Represents the move to shoot the ball at the enemy goal using a normal attack,
+    /// once the goal is in range.
+    ShootBall,
+    /// Represents the move to shoot the ball at the enemy goal using the super, once the
+    /// goal is in range.
+    UltShootBall,
+}
+
+#[cfg(test)]
+mod test_brawlball {
+    use super::*;
+    use crate::gameplay::test_utils::players;
+
+    #[test]
+    fn test_goals() {
+        let mut players = players();
+        let mut brawl_ball = BrawlBall::new();
+        for player in players.iter_mut() {
+            brawl_ball.initialize_player(&mut player.state);
+        }
+
+        let score = |players: &mut Players, id| {
+            let player = players.get_mut(PlayerId(id)).unwrap();
+            player.state.mode_or_default::<BrawlBallState>().goals += 1;
+        };
+
+        score(&mut players, 1);
+        score(&mut players, 4);
+        assert!(brawl_ball.check_result(&players).is_none());
+
+        // The time runs out with the scores tied, so the next goal wins in overtime.
+        brawl_ball.round_num = REGULAR_TIME_ROUNDS;
+        assert!(brawl_ball.check_time_up(&players).is_none());
+        assert_eq!(brawl_ball.shot_weights(8, false), [1, 1]);
+
+        score(&mut players, 5);
+        let result = GameResult::Decisive { winner: TeamId(1), losers: vec![TeamId(0)] };
+        assert_eq!(brawl_ball.check_result(&players), Some(result));
+
+        brawl_ball.round_num = 0;
+        assert_eq!(brawl_ball.shot_weights(8, false), [3, 1]);
+        score(&mut players, 2);
+        score(&mut players, 3);
+        let result = GameResult::Decisive { winner: TeamId(0), losers: vec![TeamId(1)] };
+        assert_eq!(brawl_ball.check_result(&players), Some(result));
+    }
+
+    #[test]
+    fn test_ball_range() {
+        let mut players = players();
+        let mut brawl_ball = BrawlBall::new();
+        brawl_ball.ball = Position::new(7, 7);
+        brawl_ball.goals.insert(TeamId(0), Position::new(7, 14));
+        brawl_ball.goals.insert(TeamId(1), Position::new(7, 0));
+
+        let has_move =
+            |brawl_ball: &BrawlBall, players: &Players, f: fn(&BrawlBallMove) -> bool| {
+                brawl_ball.possible_moves(PlayerId(1), players).iter().any(f)
+            };
+
+        // The ball is too far to be picked up.
+        assert!(!has_move(&brawl_ball, &players, |m| matches!(m, BrawlBallMove::PickUpBall)));
+        assert!(has_move(&brawl_ball, &players, |m| matches!(m, BrawlBallMove::MoveToBall)));
+
+        players.get_mut(PlayerId(1)).unwrap().state.position = Position::new(7, 8);
+        assert!(has_move(&brawl_ball, &players, |m| matches!(m, BrawlBallMove::PickUpBall)));
+
+        // The goal is out of the range of Shelly's attack till she dribbles closer.
+        brawl_ball.carrier = Some(PlayerId(1));
+        assert!(!has_move(&brawl_ball, &players, |m| matches!(m, BrawlBallMove::ShootBall)));
+
+        players.get_mut(PlayerId(1)).unwrap().state.position = Position::new(7, 6);
+        assert!(has_move(&brawl_ball, &players, |m| matches!(m, BrawlBallMove::ShootBall)));
+        assert_eq!(brawl_ball.ball(&players), Position::new(7, 6));
+    }
+}
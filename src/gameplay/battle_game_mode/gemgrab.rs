@@ -1,24 +1,40 @@
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use super::{BattleGameMode, GeneralMove, Moves};
-use crate::error::{Error, Result};
-use crate::gameplay::player::{Player, PlayerState};
-use crate::gameplay::{GameHandler, GameResult, Players};
-use crate::utils::rng;
+use crate::error::Result;
+use crate::gameplay::player::{PlayerId, PlayerState, Position, TeamId};
+use crate::gameplay::{GameEvent, GameHandler, GameResult, Players};
+use crate::utils::rng::{self, GameRng};
+
+/// The number of gems a team needs to collect to win.
+const GEMS_TO_WIN: u32 = 10;
+/// The number of rounds after which the game times out.
+const MAX_ROUNDS: u8 = 75;
+/// The number of tiles from which a player can collect a gem from the gem mine.
+const MINE_RANGE: u32 = 1;
 
 /// Represents Gem Grab.
 ///
-/// Gem Grab is a 1v1 gamemode (3v3 in-game) where you need to collect 10 gems
-/// before the enemy to win. At the center of the map, a gem mine spits out a gem
-/// every few seconds.
+/// Gem Grab is a team gamemode (3v3 in-game) where your team needs to collect 10 gems
+/// before the enemy team to win. At the center of the map, a gem mine spits out a gem
+/// every few seconds. A player needs to stand next to the gem mine to collect a gem.
 ///
 /// If a player dies, he drops all his gems where he died. He respawns at the back of the map
 /// after one game round.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug)]
 #[non_exhaustive]
 pub struct GemGrab {
     /// Number of dropped gems available.
-    dropped: u8,
+    dropped: u32,
+    /// The position of the gem mine.
+    mine: Position,
+}
+
+impl Default for GemGrab {
+    fn default() -> Self {
+        Self { dropped: 0, mine: Position::new(0, 0) }
+    }
 }
 
 impl GemGrab {
@@ -28,13 +44,12 @@ impl GemGrab {
     }
 
     /// Add gems to dropped amount.
-    fn drop_gems(&mut self, gems: u8) {
+    fn drop_gems(&mut self, gems: u32) {
         self.dropped += gems;
     }
 
     fn initialize_player(&self, player: &mut PlayerState) {
-        player.extra.insert("gems", 0);
-        player.extra.insert("dropped", 0);
+        player.mode_state = GemGrabState::new().into();
     }
 
     /// Runs Gem Grab.
@@ -42,155 +57,177 @@ impl GemGrab {
         mut self,
         players: &mut Players,
         handler: &dyn GameHandler,
+        rng: &mut GameRng,
     ) -> Result<GameResult> {
-        self.initialize_player(&mut players.0.state);
-        self.initialize_player(&mut players.1.state);
+        self.mine = players.arena().gem_mine();
 
+        for player in players.iter_mut() {
+            self.initialize_player(&mut player.state);
+        }
+
+        let turn_order = players.turn_order();
         let mut result = None;
         let mut round_num = 0;
 
-        while round_num < 150 {
-            let (first, second) = if round_num % 2 == 0 {
-                (&mut players.0, &mut players.1)
-            } else {
-                (&mut players.1, &mut players.0)
-            };
-
-            if first.state.status.is_respawning() {
-                BattleGameMode::dispatch_respawning_message(&first.id, handler).await?;
-            } else {
-                first.regenerate_ammo(round_num);
-                BattleGameMode::heal(first, round_num);
-
-                if first.state.is_stunned {
-                    BattleGameMode::handle_stun(first, &second.id, handler).await?;
-                    round_num += 1;
-                    continue;
+        'game: while round_num < MAX_ROUNDS {
+            for &player_id in &turn_order {
+                if BattleGameMode::start_turn(player_id, players, round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::GemGrab(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
+
+                    self.handle_move(&user_move, player_id, players, handler, rng).await?;
                 }
 
-                let possible_moves = self.possible_moves(first, second);
-                let move_idx =
-                    handler.get_move_idx(Moves::GemGrab(&possible_moves), first, second).await?;
-                let user_move = if let Some(m) = possible_moves.get(move_idx) {
-                    m
-                } else {
-                    return Err(Error::ResponseError(Some(String::from(
-                        "Invalid move: index out of bounds.",
-                    ))));
-                };
-
-                self.handle_move(user_move, first, second).await;
-
-                if second.state.health == 0 {
-                    second.respawn();
+                for defeated_id in BattleGameMode::defeated(players) {
+                    if let Some(defeated) = players.get_mut(defeated_id) {
+                        let gems = &mut defeated.state.mode_or_default::<GemGrabState>().gems;
 
-                    let gems = second.state.extra.entry("gems").or_insert(0);
+                        // This works because remainder when division by 2 is always 0 or 1.
+                        let dropped = gems.div_euclid(2) + gems.rem_euclid(2);
+                        *gems -= dropped;
 
-                    // This works because remainder when division by 2 is always 0 or 1.
-                    let dropped = gems.div_euclid(2) + gems.rem_euclid(2);
-                    *gems -= dropped;
+                        self.drop_gems(dropped);
 
-                    self.drop_gems(dropped);
+                        if dropped > 0 {
+                            let event =
+                                GameEvent::GemsDropped { player: defeated_id, gems: dropped };
+                            BattleGameMode::notify(&event, &[defeated_id], players, handler)
+                                .await?;
+                        }
+                    }
 
-                    handler.info(&first.id, "Opponent defeated! Respawning next round.").await?;
-                    handler.info(&second.id, "You are defeated! Respawning next round.").await?;
-
-                    round_num += 1;
-                    continue;
+                    BattleGameMode::respawn_defeated(defeated_id, player_id, players, handler)
+                        .await?;
                 }
-            }
 
-            if let Some(res) = self.check_result(first, second) {
-                result = Some(res);
-                break;
+                if let Some(res) = self.check_result(players) {
+                    result = Some(res);
+                    break 'game;
+                }
             }
 
             round_num += 1;
         }
 
-        let final_result = if let Some(result) = result {
-            result
-        } else {
-            BattleGameMode::time_out(&players.0.id, &players.1.id, handler).await?;
-            GameResult::Draw
-        };
-
-        Ok(final_result)
+        BattleGameMode::result(result, players, handler).await
     }
 
-    fn possible_moves(&self, first: &Player, second: &Player) -> Vec<GemGrabMove> {
-        let mut moves = vec![GemGrabMove::General(GeneralMove::Dodge), GemGrabMove::CollectGem];
-
-        let can_attack = first.can_attack();
-        let can_super = first.can_super();
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<GemGrabMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
 
-        if !second.state.status.is_respawning() {
-            if can_attack {
-                moves.push(GemGrabMove::General(GeneralMove::Attack));
-            }
+        let mut moves: Vec<GemGrabMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(GemGrabMove::General)
+            .collect();
 
-            if can_super {
-                moves.push(GemGrabMove::General(GeneralMove::Ult));
-            }
-        } else {
-            moves.push(GemGrabMove::CollectDroppedGems);
+        if player.state.position.tiles_from(&self.mine) <= MINE_RANGE {
+            moves.push(GemGrabMove::CollectGem);
+        } else if player.move_range() > 0 {
+            moves.push(GemGrabMove::MoveToMine);
         }
 
-        if second.state.spawn.is_some() {
-            if can_attack {
-                moves.push(GemGrabMove::General(GeneralMove::AttackSpawn));
-            }
-
-            if can_super {
-                moves.push(GemGrabMove::General(GeneralMove::UltSpawn));
-            }
+        if self.dropped > 0 {
+            moves.push(GemGrabMove::CollectDroppedGems);
         }
 
         moves
     }
 
-    fn check_result(&self, first: &Player, second: &Player) -> Option<GameResult> {
-        let first_gems = *first.state.extra.get("gems").unwrap_or(&0);
-        let second_gems = *second.state.extra.get("gems").unwrap_or(&0);
+    /// Returns the total number of gems held by each team.
+    fn team_gems(&self, players: &Players) -> Vec<(TeamId, u32)> {
+        players
+            .teams()
+            .into_iter()
+            .map(|team| {
+                let gems = players
+                    .team(team)
+                    .map(|p| p.state.mode::<GemGrabState>().map_or(0, |state| state.gems))
+                    .sum();
+                (team, gems)
+            })
+            .collect()
+    }
 
-        if first_gems >= 10 && second_gems < 10 {
-            Some(GameResult::Decisive { winner: first.id, loser: second.id })
-        } else if second_gems >= 10 && first_gems < 10 {
-            Some(GameResult::Decisive { winner: second.id, loser: first.id })
-        } else if first_gems >= 10 && second_gems >= 10 {
-            Some(GameResult::Draw)
-        } else {
-            None
+    fn check_result(&self, players: &Players) -> Option<GameResult> {
+        let team_gems = self.team_gems(players);
+        let mut winners = team_gems.iter().filter(|(_, gems)| *gems >= GEMS_TO_WIN);
+
+        let (winner, _) = winners.next()?;
+        if winners.next().is_some() {
+            return Some(GameResult::Draw);
         }
+
+        let losers = team_gems.iter().map(|(team, _)| *team).filter(|t| t != winner).collect();
+
+        Some(GameResult::Decisive { winner: *winner, losers })
     }
 
     async fn handle_move(
         &mut self,
         user_move: &GemGrabMove,
-        first: &mut Player,
-        second: &mut Player,
-    ) {
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<()> {
         match user_move {
-            GemGrabMove::General(gm) => gm.handle_move(first, second).await,
+            GemGrabMove::General(gm) => return gm.handle_move(player_id, players, handler).await,
+            GemGrabMove::MoveToMine => {
+                return BattleGameMode::move_towards(player_id, self.mine, players, handler).await;
+            },
+            _ => {},
+        }
+
+        let first = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(());
+        };
+
+        let new = match user_move {
+            GemGrabMove::General(_) | GemGrabMove::MoveToMine => return Ok(()),
             GemGrabMove::CollectGem => {
                 // 75% chance of collecting a gem.
-                let new = rng::select_one(&[0, 1], &[1, 3]).unwrap_or(&0);
-
-                let gems = first.state.extra.entry("gems").or_insert(0);
-                *gems += new;
+                *rng::select_one(&[0, 1], &[1, 3], rng).unwrap_or(&0)
             },
             GemGrabMove::CollectDroppedGems => {
-                let new = rand::thread_rng().gen_range(0..self.dropped);
-                self.dropped -= new;
-
-                let gems = first.state.extra.entry("gems").or_insert(0);
-                *gems += new;
-
+                let new = rng.gen_range(0..=self.dropped);
                 self.dropped = 0;
+
+                new
             },
-        }
+        };
 
-        second.state.is_invincibile = false;
+        first.state.mode_or_default::<GemGrabState>().gems += new;
+
+        let event = GameEvent::GemsCollected { player: player_id, gems: new };
+        BattleGameMode::notify(&event, &[player_id], players, handler).await
+    }
+}
+
+/// Represents the state of a player in Gem Grab.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct GemGrabState {
+    /// The number of gems the player is holding.
+    pub gems: u32,
+}
+
+impl GemGrabState {
+    /// Creates a new [`GemGrabState`] with no gems.
+    pub fn new() -> Self {
+        Self::default()
     }
 }
 
@@ -199,7 +236,10 @@ impl GemGrab {
 pub enum GemGrabMove {
     /// Represents a general move.
     General(GeneralMove),
-    /// Represents the move to collect gem.
+    /// Represents the move towards the gem mine.
+    MoveToMine,
+    /// Represents the move to collect a gem from the gem mine, once the player is next
+    /// to it.
     CollectGem,
     /// Represents the move to collect dropped gems.
     CollectDroppedGems,
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use super::{BattleGameMode, GeneralMove, Moves};
+use crate::error::Result;
+use crate::gameplay::player::{PlayerId, Position, TeamId};
+use crate::gameplay::{GameEvent, GameHandler, GameResult, Players};
+use crate::model::map::Tile;
+use crate::utils::rng::GameRng;
+
+/// The health of a safe at the start of the game.
+const SAFE_HEALTH: u32 = 40000;
+/// The number of rounds after which the game times out.
+const MAX_ROUNDS: u8 = 75;
+
+/// Represents Heist.
+///
+/// Heist is a team gamemode (3v3 in-game) where each team has a safe near its spawn
+/// point that they need to protect. A safe can only be attacked from within the range
+/// of the attack and in the line of sight. The team whose safe is the last one standing wins.
+///
+/// A player can guard their team's safe instead of attacking, unless a teammate is
+/// already guarding it. Attacks on a guarded safe deal half the damage and the attacker is hit back by the defender. If the
+/// time runs out, the team whose safe took the least damage wins.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Heist {
+    /// The round number of the game.
+    round_num: u8,
+    /// The safes of the teams.
+    safes: HashMap<TeamId, Safe>,
+}
+
+impl Heist {
+    /// Creates a new [`Heist`] object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs Heist.
+    pub async fn run(
+        mut self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        _rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        for team in players.teams() {
+            if let Some(spawn) = players.team_spawn(team) {
+                let position = players.arena().structure(Tile::Safe, spawn);
+                self.safes.insert(team, Safe::new(SAFE_HEALTH, position));
+            }
+        }
+
+        let turn_order = players.turn_order();
+        let mut result = None;
+
+        'game: while self.round_num < MAX_ROUNDS {
+            for &player_id in &turn_order {
+                // The guard only lasts till the next turn of the player.
+                for safe in self.safes.values_mut() {
+                    if safe.guard == Some(player_id) {
+                        safe.guard = None;
+                    }
+                }
+
+                let mut defender = None;
+
+                if BattleGameMode::start_turn(player_id, players, self.round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::Heist(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
+
+                    defender = self.handle_move(&user_move, player_id, players, handler).await?;
+
+                    if let HeistMove::AttackSafe(team) | HeistMove::UltSafe(team) = user_move {
+                        let health = self.safes.get(&team).map_or(0, |s| s.health);
+                        let event = GameEvent::SafeDamaged { team, health };
+                        BattleGameMode::broadcast(&event, players, handler).await?;
+                    }
+                }
+
+                for defeated_id in BattleGameMode::defeated(players) {
+                    // The attacker can be defeated by the defender of a guarded safe.
+                    let attacker = match defender {
+                        Some(defender) if defeated_id == player_id => defender,
+                        _ => player_id,
+                    };
+
+                    BattleGameMode::respawn_defeated(defeated_id, attacker, players, handler)
+                        .await?;
+                }
+
+                if let Some(res) = self.check_result() {
+                    result = Some(res);
+                    break 'game;
+                }
+            }
+
+            self.round_num += 1;
+        }
+
+        if result.is_none() {
+            result = self.check_time_up();
+        }
+
+        BattleGameMode::result(result, players, handler).await
+    }
+
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<HeistMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
+
+        let mut moves: Vec<HeistMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(HeistMove::General)
+            .collect();
+
+        if self.safes.get(&player.team).is_some_and(|safe| !safe.is_guarded()) {
+            moves.push(HeistMove::DefendSafe);
+        }
+
+        let can_attack = player.can_attack();
+        // Only a super which deals damage can break a safe.
+        let can_super =
+            player.can_super() && player.brawler_state.brawler.info().ult.damage.is_some();
+
+        for team in players.teams() {
+            let safe = match self.safes.get(&team) {
+                Some(safe) if team != player.team && !safe.is_destroyed() => safe,
+                _ => continue,
+            };
+
+            // The safe can only be hit from within range and in the line of sight.
+            let in_sight =
+                players.arena().has_line_of_sight(&player.state.position, &safe.position);
+            let reaches = |is_ult| in_sight && player.in_range(&safe.position, is_ult);
+
+            if can_attack && reaches(false) {
+                moves.push(HeistMove::AttackSafe(team));
+            }
+
+            if can_super && reaches(true) {
+                moves.push(HeistMove::UltSafe(team));
+            }
+
+            if !reaches(false) && player.move_range() > 0 {
+                moves.push(HeistMove::MoveToSafe(team));
+            }
+        }
+
+        moves
+    }
+
+    /// Returns the result if only one safe or no safe is left standing.
+    fn check_result(&self) -> Option<GameResult> {
+        let mut standing = self.safes.iter().filter(|(_, safe)| !safe.is_destroyed());
+
+        match (standing.next(), standing.next()) {
+            (None, _) => Some(GameResult::Draw),
+            (Some((winner, _)), None) => {
+                let mut losers: Vec<TeamId> =
+                    self.safes.keys().copied().filter(|t| t != winner).collect();
+                losers.sort();
+
+                Some(GameResult::Decisive { winner: *winner, losers })
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the result at timeout based on the damage taken by the safes.
+    ///
+    /// Returns `None` if more than one safe took the least amount of damage.
+    fn check_time_up(&self) -> Option<GameResult> {
+        // The less damage a safe took, the higher the score of its team.
+        let mut scores: Vec<(TeamId, u32)> =
+            self.safes.iter().map(|(team, safe)| (*team, u32::MAX - safe.damage_taken())).collect();
+        scores.sort();
+
+        BattleGameMode::result_from_scores(&scores)
+    }
+
+    /// Handles the move of a user.
+    ///
+    /// Returns the ID of the defender who hit back at the player, if any.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn handle_move(
+        &mut self,
+        user_move: &HeistMove,
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<Option<PlayerId>> {
+        let team = match user_move {
+            HeistMove::General(gm) => {
+                gm.handle_move(player_id, players, handler).await?;
+                return Ok(None);
+            },
+            HeistMove::DefendSafe => {
+                let team = players.get(player_id).map(|p| p.team);
+                if let Some(safe) = team.and_then(|t| self.safes.get_mut(&t)) {
+                    safe.guard = Some(player_id);
+                }
+
+                return Ok(None);
+            },
+            HeistMove::MoveToSafe(team) => {
+                if let Some(safe) = self.safes.get(team) {
+                    BattleGameMode::move_towards(player_id, safe.position, players, handler)
+                        .await?;
+                }
+
+                return Ok(None);
+            },
+            HeistMove::AttackSafe(team) | HeistMove::UltSafe(team) => *team,
+        };
+
+        // The defender hits back at the attacker with a normal attack.
+        let defender = self
+            .safes
+            .get(&team)
+            .and_then(|safe| safe.guard)
+            .and_then(|guard| players.get(guard))
+            .filter(|guard| !guard.state.is_respawning())
+            .map(|guard| {
+                let state = &guard.brawler_state;
+                let info = state.brawler.info();
+                (guard.id, state.brawler.buff_stat(info.attack.damage, state.level))
+            });
+
+        let is_ult = matches!(user_move, HeistMove::UltSafe(_));
+        let damage = BattleGameMode::damage_structure(player_id, players, is_ult, self.round_num);
+
+        if let Some(safe) = self.safes.get_mut(&team) {
+            // The damage is halved when the safe is guarded.
+            safe.damage(if defender.is_some() { damage / 2 } else { damage });
+        }
+
+        let (defender, hit_back) = match defender {
+            Some(defender) => defender,
+            None => return Ok(None),
+        };
+
+        let first = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(None);
+        };
+
+        let health = first.state.health;
+        first.state.damage(hit_back);
+        first.state.last_attack_round = self.round_num;
+
+        let event = GameEvent::Damaged {
+            attacker: defender,
+            target: player_id,
+            damage: health.saturating_sub(first.state.health),
+        };
+        BattleGameMode::notify(&event, &[defender, player_id], players, handler).await?;
+
+        Ok(Some(defender))
+    }
+}
+
+/// Represents a team's safe in Heist.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct Safe {
+    /// Maximum health points the safe can have.
+    pub max_health: u32,
+    /// The safe's current health points.
+    pub health: u32,
+    /// The player guarding the safe, if any.
+    pub guard: Option<PlayerId>,
+    /// The position of the safe in the arena.
+    pub position: Position,
+}
+
+impl Safe {
+    /// Creates a new [`Safe`] with given health at given position.
+    pub fn new(health: u32, position: Position) -> Self {
+        Self { max_health: health, health, guard: None, position }
+    }
+
+    /// Applies `amount` damage to the safe.
+    pub fn damage(&mut self, amount: u32) {
+        self.health = self.health.saturating_sub(amount);
+    }
+
+    /// Returns the total damage taken by the safe.
+    pub fn damage_taken(&self) -> u32 {
+        self.max_health - self.health
+    }
+
+    /// Checks if the safe is being guarded by a player.
+    pub fn is_guarded(&self) -> bool {
+        self.guard.is_some()
+    }
+
+    /// Checks if the safe is destroyed.
+    pub fn is_destroyed(&self) -> bool {
+        self.health == 0
+    }
+}
+
+/// Represents a user move in Heist.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum HeistMove {
+    /// Represents a general move.
+    General(GeneralMove),
+    /// Represents the move towards the safe of the given team.
+    MoveToSafe(TeamId),
+    /// Represents the move to attack the safe of the given team using a normal attack.
+    AttackSafe(TeamId),
+    /// Represents the move to attack the safe of the given team using the super.
+    UltSafe(TeamId),
+    /// Represents the move to guard the team's safe till the next turn.
+    DefendSafe,
+}
+
+#[cfg(test)]
+mod test_heist {
+    use super::*;
+    use crate::gameplay::replay::ReplayHandler;
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::gameplay::Arena;
+    use crate::model::map::Map;
+
+    #[test]
+    fn test_guard() {
+        let mut players = players();
+        let handler = ReplayHandler::new(Vec::new());
+        let mut heist = Heist::new();
+        heist.safes.insert(TeamId(1), Safe::new(SAFE_HEALTH, Position::new(7, 0)));
+
+        let defend = heist.handle_move(&HeistMove::DefendSafe, PlayerId(4), &mut players, &handler);
+        assert_eq!(block_on(defend).unwrap(), None);
+
+        // A teammate can't guard a safe which is already guarded.
+        let can_defend = |heist: &Heist, players: &Players| {
+            heist
+                .possible_moves(PlayerId(5), players)
+                .iter()
+                .any(|m| matches!(m, HeistMove::DefendSafe))
+        };
+        assert!(!can_defend(&heist, &players));
+
+        let attack = HeistMove::AttackSafe(TeamId(1));
+        let defender = heist.handle_move(&attack, PlayerId(1), &mut players, &handler);
+        assert_eq!(block_on(defender).unwrap(), Some(PlayerId(4)));
+        assert_eq!(heist.safes[&TeamId(1)].health, SAFE_HEALTH - 1050);
+
+        heist.safes.get_mut(&TeamId(1)).unwrap().guard = None;
+        assert!(can_defend(&heist, &players));
+        let defender = heist.handle_move(&attack, PlayerId(1), &mut players, &handler);
+        assert_eq!(block_on(defender).unwrap(), None);
+        assert_eq!(heist.safes[&TeamId(1)].health, SAFE_HEALTH - 1050 - 2100);
+
+        // The defender hit back at the attacker with a normal attack.
+        let attacker = &players.get(PlayerId(1)).unwrap().state;
+        assert_eq!(attacker.health, attacker.max_health - 420);
+        assert_eq!(attacker.attacks, 2);
+    }
+
+    #[test]
+    fn test_time_up() {
+        let mut heist = Heist::new();
+        heist.safes.insert(TeamId(0), Safe::new(SAFE_HEALTH, Position::new(7, 0)));
+        heist.safes.insert(TeamId(1), Safe::new(SAFE_HEALTH, Position::new(7, 0)));
+        assert_eq!(heist.check_time_up(), None);
+
+        heist.safes.get_mut(&TeamId(0)).unwrap().damage(1050);
+        assert_eq!(
+            heist.check_time_up(),
+            Some(GameResult::Decisive { winner: TeamId(1), losers: vec![TeamId(0)] })
+        );
+    }
+
+    #[test]
+    fn test_safe_in_sight() {
+        let mut players = players();
+        let mut heist = Heist::new();
+        heist.safes.insert(TeamId(1), Safe::new(SAFE_HEALTH, Position::new(1, 0)));
+        let moves = |heist: &Heist, players: &Players| heist.possible_moves(PlayerId(1), players);
+
+        // The safe is in range but behind the wall.
+        let map = Map::from_ascii("Wall", ".S1..\n.....\n#####\n..0..\n.....").unwrap();
+        players.set_arena(Arena::from_map(map));
+        assert!(!moves(&heist, &players).iter().any(|m| matches!(m, HeistMove::AttackSafe(_))));
+        assert!(moves(&heist, &players).iter().any(|m| matches!(m, HeistMove::MoveToSafe(_))));
+
+        let map = Map::from_ascii("Open", ".S1..\n.....\n.....\n..0..\n.....").unwrap();
+        players.set_arena(Arena::from_map(map));
+        assert!(moves(&heist, &players).iter().any(|m| matches!(m, HeistMove::AttackSafe(_))));
+        assert!(!moves(&heist, &players).iter().any(|m| matches!(m, HeistMove::MoveToSafe(_))));
+    }
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BattleGameMode, GeneralMove, Moves};
+use crate::error::Result;
+use crate::gameplay::player::{PlayerId, PlayerState, Position, TeamId};
+use crate::gameplay::{GameHandler, GameResult, Players};
+use crate::utils::rng::GameRng;
+
+/// The zone control percentage required to win the game.
+const CONTROL_TO_WIN: u32 = 100;
+/// The zone control percentage gained by a player every turn they hold the zone.
+const CONTROL_PER_ROUND: u32 = 2;
+/// The radius of the zone.
+const ZONE_RADIUS: f32 = 1.5;
+/// The number of rounds after which the game times out.
+const MAX_ROUNDS: u8 = 75;
+
+/// Represents Hot Zone.
+///
+/// Hot Zone is a team gamemode (3v3 in-game) where you need to control the zone at
+/// the center of the arena. A player standing in the zone gains control for their team
+/// every turn, as long as no enemy is standing in it. The zone is contested when
+/// players of different teams stand in it, and no one gains control until one of
+/// the teams leaves or is defeated.
+///
+/// The first team to reach 100% control wins. If the time runs out, the team
+/// with more control wins.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct HotZone {
+    /// The round number of the game.
+    round_num: u8,
+    /// The center of the zone.
+    center: Position,
+}
+
+impl Default for HotZone {
+    fn default() -> Self {
+        Self { round_num: 0, center: Position::new(0, 0) }
+    }
+}
+
+impl HotZone {
+    /// Creates a new [`HotZone`] object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn initialize_player(&mut self, player: &mut PlayerState) {
+        player.mode_state = HotZoneState::new().into();
+    }
+
+    /// Runs Hot Zone.
+    pub async fn run(
+        mut self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        _rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        self.center = players.arena().center();
+
+        for player in players.iter_mut() {
+            self.initialize_player(&mut player.state);
+        }
+
+        let turn_order = players.turn_order();
+        let mut result = None;
+
+        'game: while self.round_num < MAX_ROUNDS {
+            for &player_id in &turn_order {
+                if BattleGameMode::start_turn(player_id, players, self.round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::HotZone(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
+
+                    self.handle_move(&user_move, player_id, players, handler).await?;
+                }
+
+                for defeated_id in BattleGameMode::defeated(players) {
+                    BattleGameMode::respawn_defeated(defeated_id, player_id, players, handler)
+                        .await?;
+                }
+
+                // Stunned players keep holding the zone.
+                self.capture_zone(player_id, players);
+
+                if let Some(res) = self.check_result(players) {
+                    result = Some(res);
+                    break 'game;
+                }
+            }
+
+            self.round_num += 1;
+        }
+
+        if result.is_none() {
+            result = BattleGameMode::result_from_scores(&self.team_control(players));
+        }
+
+        BattleGameMode::result(result, players, handler).await
+    }
+
+    /// Checks if the player is standing in the zone.
+    fn in_zone(&self, player: &PlayerState) -> bool {
+        !player.is_respawning() && player.position.distance(&self.center) <= ZONE_RADIUS
+    }
+
+    /// Increases the control of the player if no enemy is standing in the zone with them.
+    fn capture_zone(&self, player_id: PlayerId, players: &mut Players) {
+        let contested = players.enemies(player_id).any(|p| self.in_zone(&p.state));
+
+        if let Some(player) = players.get_mut(player_id) {
+            if self.in_zone(&player.state) && !contested {
+                let state = player.state.mode_or_default::<HotZoneState>();
+                state.control = CONTROL_TO_WIN.min(state.control + CONTROL_PER_ROUND);
+            }
+        }
+    }
+
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<HotZoneMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
+
+        let mut moves: Vec<HotZoneMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(HotZoneMove::General)
+            .collect();
+
+        // A player who can't move can neither enter nor leave the zone.
+        if player.move_range() == 0 {
+            return moves;
+        }
+
+        if self.in_zone(&player.state) {
+            moves.push(HotZoneMove::LeaveZone);
+        } else {
+            moves.push(HotZoneMove::EnterZone);
+        }
+
+        moves
+    }
+
+    /// Returns the total zone control gained by each team.
+    fn team_control(&self, players: &Players) -> Vec<(TeamId, u32)> {
+        players
+            .teams()
+            .into_iter()
+            .map(|team| {
+                let control = players
+                    .team(team)
+                    .map(|p| p.state.mode::<HotZoneState>().map_or(0, |state| state.control))
+                    .sum();
+                (team, control)
+            })
+            .collect()
+    }
+
+    fn check_result(&self, players: &Players) -> Option<GameResult> {
+        let team_control = self.team_control(players);
+        let (winner, _) = team_control.iter().find(|(_, control)| *control >= CONTROL_TO_WIN)?;
+
+        let losers = team_control.iter().map(|(team, _)| *team).filter(|t| t != winner).collect();
+
+        Some(GameResult::Decisive { winner: *winner, losers })
+    }
+
+    async fn handle_move(
+        &mut self,
+        user_move: &HotZoneMove,
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        if let HotZoneMove::General(gm) = user_move {
+            return gm.handle_move(player_id, players, handler).await;
+        }
+
+        let to = match user_move {
+            HotZoneMove::LeaveZone => match players.spawn_point(player_id) {
+                Some(spawn) => spawn,
+                None => return Ok(()),
+            },
+            _ => self.center,
+        };
+
+        BattleGameMode::move_towards(player_id, to, players, handler).await
+    }
+}
+
+/// Represents the state of a player in Hot Zone.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct HotZoneState {
+    /// The zone control percentage the player has gained for their team.
+    pub control: u32,
+}
+
+impl HotZoneState {
+    /// Creates a new [`HotZoneState`] with no zone control.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Represents a user move in Hot Zone.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum HotZoneMove {
+    /// Represents a general move.
+    General(GeneralMove),
+    /// Represents the move towards the zone, which enters it once the player is close.
+    EnterZone,
+    /// Represents the move to leave the zone and retreat towards the spawn point.
+    LeaveZone,
+}
+
+#[cfg(test)]
+mod test_hotzone {
+    use super::*;
+    use crate::gameplay::status::StatusEffect;
+    use crate::gameplay::test_utils::players;
+
+    #[test]
+    fn test_capture_zone() {
+        let mut players = players();
+        let hot_zone = HotZone { round_num: 0, center: players.arena().center() };
+        let control = |players: &Players| hot_zone.team_control(players)[0].1;
+
+        players.get_mut(PlayerId(1)).unwrap().state.position = hot_zone.center;
+        hot_zone.capture_zone(PlayerId(1), &mut players);
+        assert_eq!(control(&players), CONTROL_PER_ROUND);
+
+        // An enemy in the zone contests it.
+        players.get_mut(PlayerId(4)).unwrap().state.position = hot_zone.center;
+        hot_zone.capture_zone(PlayerId(1), &mut players);
+        hot_zone.capture_zone(PlayerId(4), &mut players);
+        assert_eq!(hot_zone.team_control(&players), vec![
+            (TeamId(0), CONTROL_PER_ROUND),
+            (TeamId(1), 0)
+        ]);
+
+        players.get_mut(PlayerId(4)).unwrap().respawn();
+        for _ in 0..CONTROL_TO_WIN {
+            hot_zone.capture_zone(PlayerId(1), &mut players);
+        }
+        assert_eq!(control(&players), CONTROL_TO_WIN);
+
+        let result = GameResult::Decisive { winner: TeamId(0), losers: vec![TeamId(1)] };
+        assert_eq!(hot_zone.check_result(&players), Some(result));
+
+        // A player who is slowed completely can't move into the zone.
+        let is_zone_move = |m: &HotZoneMove| !matches!(m, HotZoneMove::General(_));
+        assert!(hot_zone.possible_moves(PlayerId(2), &players).iter().any(is_zone_move));
+        let effects = &mut players.get_mut(PlayerId(2)).unwrap().state.effects;
+        effects.apply(StatusEffect::Slow { percent: 100 }, 1);
+        assert!(!hot_zone.possible_moves(PlayerId(2), &players).iter().any(is_zone_move));
+    }
+}
@@ -1,19 +1,34 @@
+use serde::{Deserialize, Serialize};
+
 use super::{BattleGameMode, GeneralMove, Moves};
 use crate::error::{Error, Result};
-use crate::gameplay::player::{Player, PlayerState};
-use crate::gameplay::{GameHandler, GameResult, Players};
-use crate::utils::rng;
+use crate::gameplay::player::{PlayerId, PlayerState, TeamId};
+use crate::gameplay::status::StatusEffect;
+use crate::gameplay::{GameEvent, GameHandler, GameResult, Players};
+use crate::utils::rng::{self, GameRng};
 
 /// The round number at which the poison effect begins.
-const POISON_ROUND_NUM: u8 = 40;
-/// The amount of damage done due to the poison effect.
-const POISON_DAMAGE: u32 = 100;
+const POISON_ROUND_NUM: u8 = 20;
+/// The amount of damage done due to the poison effect every round.
+const POISON_DAMAGE: u32 = 200;
+/// The number of rounds after which the game times out.
+const MAX_ROUNDS: u8 = 75;
 
-#[derive(Clone, Copy, Debug, Default)]
+/// Represents Showdown.
+///
+/// Showdown is a battle royale gamemode where every team fights for itself, solo or
+/// in duos. The poison closes in after a few rounds and damages everyone still alive.
+///
+/// The teams are ranked in the reverse order of their elimination. If the time runs
+/// out, the teams still alive are ranked by their total health. A team which forfeits
+/// is eliminated at once, so the teams eliminated before it keep their placements.
+#[derive(Clone, Debug, Default)]
 #[non_exhaustive]
 pub struct Showdown {
     /// The round number of the game.
     round_num: u8,
+    /// The teams which have been eliminated, in the order of their elimination.
+    eliminated: Vec<TeamId>,
 }
 
 impl Showdown {
@@ -23,7 +38,7 @@ impl Showdown {
     }
 
     fn initialize_player(&self, player: &mut PlayerState) {
-        player.extra.insert("powerups", 0);
+        player.mode_state = ShowdownState::new().into();
     }
 
     /// Runs Showdown.
@@ -31,78 +46,144 @@ impl Showdown {
         mut self,
         players: &mut Players,
         handler: &dyn GameHandler,
+        rng: &mut GameRng,
     ) -> Result<GameResult> {
-        self.initialize_player(&mut players.0.state);
-        self.initialize_player(&mut players.1.state);
+        match self.play(players, handler, rng).await {
+            Err(Error::Forfeit(id) | Error::AfkForfeit(id)) => {
+                if let Some(team) = players.get(id).map(|p| p.team) {
+                    if !self.eliminated.contains(&team) {
+                        self.eliminated.push(team);
+                    }
+                }
 
-        let mut result = None;
+                BattleGameMode::forfeit(id, &self.ranking(players), players)
+            },
+            result => result,
+        }
+    }
 
-        while self.round_num < 150 {
-            let (first, second) = if self.round_num % 2 == 0 {
-                (&mut players.0, &mut players.1)
-            } else {
-                (&mut players.1, &mut players.0)
-            };
+    /// Plays the rounds of Showdown till at most one team is left alive or the time
+    /// runs out.
+    async fn play(
+        &mut self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        for player in players.iter_mut() {
+            self.initialize_player(&mut player.state);
+        }
 
-            first.regenerate_ammo(self.round_num);
-            BattleGameMode::heal(first, self.round_num);
+        let turn_order = players.turn_order();
+        let mut result = None;
 
-            if first.state.is_stunned {
-                BattleGameMode::handle_stun(first, &second.id, handler).await?;
-                self.round_num += 1;
-                continue;
-            }
+        'game: while self.round_num < MAX_ROUNDS {
+            for &player_id in &turn_order {
+                if BattleGameMode::start_turn(player_id, players, self.round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::Showdown(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
 
-            let user_move = self.get_user_move(first, second, handler).await?;
+                    self.handle_move(&user_move, player_id, players, handler, rng).await?;
+                }
 
-            self.handle_move(&user_move, first, second).await;
-            self.poison_effect(&mut first.state, &mut second.state);
+                self.eliminate(players, handler).await?;
 
-            if let Some(res) = self.check_result(first, second) {
-                result = Some(res);
-                break;
+                if let Some(res) = self.check_result(players) {
+                    result = Some(res);
+                    break 'game;
+                }
             }
 
+            self.poison_effect(players);
             self.round_num += 1;
         }
 
-        BattleGameMode::result(result, players, handler).await
+        // The game never ends in a draw, the teams alive at timeout are ranked by health.
+        Ok(result.unwrap_or_else(|| self.placements(players)))
     }
 
-    fn possible_moves(&self, first: &Player, second: &Player) -> Vec<ShowdownMove> {
-        let mut moves =
-            vec![ShowdownMove::General(GeneralMove::Dodge), ShowdownMove::CollectPowerUp];
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<ShowdownMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
+
+        let mut moves: Vec<ShowdownMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(ShowdownMove::General)
+            .collect();
+        moves.push(ShowdownMove::CollectPowerUp);
 
-        let can_attack = first.can_attack();
-        let can_super = first.can_super();
-        if can_attack {
-            moves.push(ShowdownMove::General(GeneralMove::Attack));
-        }
+        moves
+    }
 
-        if can_super {
-            moves.push(ShowdownMove::General(GeneralMove::Ult));
-        }
+    /// Returns the teams which have at least one player alive.
+    fn teams_alive(&self, players: &Players) -> Vec<TeamId> {
+        players
+            .teams()
+            .into_iter()
+            .filter(|team| players.team(*team).any(|p| !p.state.is_dead()))
+            .collect()
+    }
 
-        if second.state.spawn.is_some() {
-            if can_attack {
-                moves.push(ShowdownMove::General(GeneralMove::AttackSpawn));
-            }
+    /// Records the elimination of the teams which have no player alive and informs
+    /// all the players about it.
+    ///
+    /// Teams eliminated at the same time are recorded in their turn order.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn eliminate(&mut self, players: &Players, handler: &dyn GameHandler) -> Result<()> {
+        let alive = self.teams_alive(players);
 
-            if can_super {
-                moves.push(ShowdownMove::General(GeneralMove::UltSpawn));
+        for team in players.teams() {
+            if !alive.contains(&team) && !self.eliminated.contains(&team) {
+                self.eliminated.push(team);
+
+                let event = GameEvent::Eliminated { team };
+                BattleGameMode::broadcast(&event, players, handler).await?;
             }
         }
 
-        moves
+        Ok(())
+    }
+
+    /// Returns all the teams in the order of their placement.
+    ///
+    /// The teams still alive are placed first, ordered by their total health,
+    /// followed by the eliminated teams in the reverse order of their elimination.
+    fn ranking(&self, players: &Players) -> Vec<TeamId> {
+        let mut alive: Vec<(TeamId, u32)> = self
+            .teams_alive(players)
+            .into_iter()
+            .filter(|team| !self.eliminated.contains(team))
+            .map(|team| (team, players.team(team).map(|p| p.state.health).sum()))
+            .collect();
+        alive.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        alive
+            .into_iter()
+            .map(|(team, _)| team)
+            .chain(self.eliminated.iter().rev().copied())
+            .collect()
     }
 
-    fn check_result(&self, first: &Player, second: &Player) -> Option<GameResult> {
-        if first.state.is_alive() && second.state.is_dead() {
-            Some(GameResult::Decisive { winner: first.id, loser: second.id })
-        } else if first.state.is_dead() && second.state.is_alive() {
-            Some(GameResult::Decisive { winner: second.id, loser: first.id })
-        } else if first.state.is_dead() && second.state.is_dead() {
-            Some(GameResult::Draw)
+    /// Returns the placements of all the teams.
+    fn placements(&self, players: &Players) -> GameResult {
+        GameResult::Ranked(self.ranking(players))
+    }
+
+    /// Returns the placements once at most one team is left alive.
+    fn check_result(&self, players: &Players) -> Option<GameResult> {
+        if self.teams_alive(players).len() <= 1 {
+            Some(self.placements(players))
         } else {
             None
         }
@@ -111,47 +192,60 @@ impl Showdown {
     async fn handle_move(
         &mut self,
         user_move: &ShowdownMove,
-        first: &mut Player,
-        second: &mut Player,
-    ) {
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<()> {
         match user_move {
-            ShowdownMove::General(gm) => gm.handle_move(first, second).await,
+            ShowdownMove::General(gm) => gm.handle_move(player_id, players, handler).await,
             ShowdownMove::CollectPowerUp => {
+                let first = if let Some(player) = players.get_mut(player_id) {
+                    player
+                } else {
+                    return Ok(());
+                };
+
                 // 25% chance of collecting a power-up.
-                let new = rng::select_one(&[0, 1], &[3, 1]).unwrap_or(&0);
+                let new = *rng::select_one(&[0, 1], &[3, 1], rng).unwrap_or(&0);
 
-                let powerups = first.state.extra.entry("powerups").or_insert(0);
-                *powerups += new;
+                first.state.mode_or_default::<ShowdownState>().powerups += new;
+
+                let event = GameEvent::PowerUpsCollected { player: player_id, powerups: new };
+                BattleGameMode::notify(&event, &[player_id], players, handler).await
             },
         }
-
-        second.state.is_invincibile = false;
     }
 
-    async fn get_user_move(
-        &self,
-        first: &Player,
-        second: &Player,
-        handler: &dyn GameHandler,
-    ) -> Result<ShowdownMove> {
-        let possible_moves = self.possible_moves(first, second);
-        let move_idx =
-            handler.get_move_idx(Moves::Showdown(&possible_moves), first, second).await?;
-        if let Some(m) = possible_moves.get(move_idx) {
-            Ok(*m)
-        } else {
-            Err(Error::ResponseError(Some(String::from("invalid move: index out of bounds."))))
+    /// Poisons all the players alive at the end of a round.
+    ///
+    /// The poisoned players take the damage at the start of their next turn.
+    fn poison_effect(&self, players: &mut Players) {
+        if self.round_num < POISON_ROUND_NUM {
+            return;
         }
-    }
 
-    fn poison_effect(&self, first: &mut PlayerState, second: &mut PlayerState) {
-        if self.round_num >= POISON_ROUND_NUM {
-            first.damage(POISON_DAMAGE);
-            second.damage(POISON_DAMAGE);
+        for player in players.iter_mut().filter(|p| !p.state.is_dead()) {
+            player.state.effects.apply(StatusEffect::Poison { damage: POISON_DAMAGE }, 1);
         }
     }
 }
 
+/// Represents the state of a player in Showdown.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ShowdownState {
+    /// The number of power-ups the player has collected.
+    pub powerups: u32,
+}
+
+impl ShowdownState {
+    /// Creates a new [`ShowdownState`] with no power-ups.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 /// Represents a user move in Showdown.
 #[derive(Clone, Copy, Debug)]
 #[non_exhaustive]
@@ -161,3 +255,36 @@ pub enum ShowdownMove {
     /// Represents the move to collect power up.
     CollectPowerUp,
 }
+
+#[cfg(test)]
+mod test_showdown {
+    use super::*;
+    use crate::gameplay::replay::ReplayHandler;
+    use crate::gameplay::test_utils::{block_on, shelly};
+
+    #[test]
+    fn test_placements() {
+        let mut players =
+            Players::from_teams((1..=4).map(|id| vec![shelly(id, id == 1)]).collect());
+        let handler = ReplayHandler::new(Vec::new());
+        let mut showdown = Showdown::new();
+
+        let defeat = |players: &mut Players, showdown: &mut Showdown, id| {
+            players.get_mut(PlayerId(id)).unwrap().state.damage(u32::MAX);
+            block_on(showdown.eliminate(players, &handler)).unwrap();
+        };
+
+        defeat(&mut players, &mut showdown, 3);
+        defeat(&mut players, &mut showdown, 1);
+        players.get_mut(PlayerId(4)).unwrap().state.damage(1000);
+        assert!(showdown.check_result(&players).is_none());
+
+        // The teams alive at timeout are ranked by their health.
+        let ranked = [TeamId(1), TeamId(3), TeamId(0), TeamId(2)];
+        assert_eq!(showdown.placements(&players), GameResult::Ranked(ranked.to_vec()));
+
+        defeat(&mut players, &mut showdown, 2);
+        let ranked = [TeamId(3), TeamId(1), TeamId(0), TeamId(2)];
+        assert_eq!(showdown.check_result(&players), Some(GameResult::Ranked(ranked.to_vec())));
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{BattleGameMode, GeneralMove, Moves};
+use crate::error::Result;
+use crate::gameplay::battle_brawler::Spawn;
+use crate::gameplay::player::{PlayerId, PlayerSpawn, PlayerState, Position, TeamId};
+use crate::gameplay::{GameEvent, GameHandler, GameResult, Players};
+use crate::utils::rng::{self, GameRng};
+
+/// The number of bolts required to summon a siege robot.
+const BOLTS_FOR_ROBOT: u32 = 6;
+/// The health of an IKE turret at the start of the game.
+const IKE_HEALTH: u32 = 30000;
+/// The damage dealt by an IKE turret to the attacking robot every round.
+const IKE_DAMAGE: u32 = 600;
+/// The health of a siege robot.
+const ROBOT_HEALTH: u32 = 8000;
+/// The damage dealt by a siege robot to the enemy IKE turret every round.
+const ROBOT_DAMAGE: u32 = 1200;
+/// The number of rounds after which the game times out.
+const MAX_ROUNDS: u8 = 75;
+
+/// Represents Siege.
+///
+/// Siege is a team gamemode (3v3 in-game) where each team has an IKE turret behind
+/// its spawn point that they need to protect. Bolts are collected at the center of
+/// the map and every 6 bolts collected by a team summon a siege robot for them, which
+/// stands in front of the enemy IKE turret and attacks it at the start of every round
+/// till it is destroyed.
+///
+/// The team whose IKE turret is the last one standing wins. If the time runs out,
+/// the team whose IKE turret took the least damage wins.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Siege {
+    /// The round number of the game.
+    round_num: u8,
+    /// The IKE turrets of the teams.
+    ikes: HashMap<TeamId, IkeTurret>,
+    /// The siege robots of the teams which have summoned one.
+    robots: HashMap<TeamId, PlayerSpawn>,
+}
+
+impl Siege {
+    /// Creates a new [`Siege`] object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn initialize_player(&self, player: &mut PlayerState) {
+        player.mode_state = SiegeState::new().into();
+    }
+
+    /// Runs Siege.
+    pub async fn run(
+        mut self,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<GameResult> {
+        for player in players.iter_mut() {
+            self.initialize_player(&mut player.state);
+        }
+
+        for team in players.teams() {
+            if let Some(spawn) = players.team_spawn(team) {
+                let position = players.arena().behind(spawn);
+                self.ikes.insert(team, IkeTurret::new(IKE_HEALTH, position));
+            }
+        }
+
+        let turn_order = players.turn_order();
+        let mut result = None;
+
+        while self.round_num < MAX_ROUNDS {
+            // The robots act on their own, even if their owners can't.
+            for team in players.teams() {
+                self.robot_attack(team, players, handler).await?;
+            }
+
+            if let Some(res) = self.check_result() {
+                result = Some(res);
+                break;
+            }
+
+            for &player_id in &turn_order {
+                if BattleGameMode::start_turn(player_id, players, self.round_num, handler).await? {
+                    let possible_moves = self.possible_moves(player_id, players);
+                    let user_move = BattleGameMode::get_user_move(
+                        &possible_moves,
+                        |moves| Moves::Siege(moves),
+                        player_id,
+                        players,
+                        handler,
+                    )
+                    .await?;
+
+                    self.handle_move(&user_move, player_id, players, handler, rng).await?;
+
+                    if let Some(team) = self.summon_robot(player_id, players) {
+                        let event = GameEvent::RobotSummoned { team };
+                        BattleGameMode::broadcast(&event, players, handler).await?;
+                    }
+                }
+
+                for defeated_id in BattleGameMode::defeated(players) {
+                    BattleGameMode::respawn_defeated(defeated_id, player_id, players, handler)
+                        .await?;
+                }
+            }
+
+            self.round_num += 1;
+        }
+
+        if result.is_none() {
+            result = self.check_time_up();
+        }
+
+        BattleGameMode::result(result, players, handler).await
+    }
+
+    /// Summons a siege robot for the team of the player if the team has collected
+    /// enough bolts.
+    ///
+    /// The bolts of the team are consumed only if the team doesn't already have a robot,
+    /// and the bolts left over are kept by the player. Returns the team for which a robot
+    /// is summoned, if any.
+    fn summon_robot(&mut self, player_id: PlayerId, players: &mut Players) -> Option<TeamId> {
+        let team = players.get(player_id)?.team;
+        if self.robots.contains_key(&team) {
+            return None;
+        }
+
+        let bolts: u32 = players
+            .team(team)
+            .map(|p| p.state.mode::<SiegeState>().map_or(0, |state| state.bolts))
+            .sum();
+        if bolts < BOLTS_FOR_ROBOT {
+            return None;
+        }
+
+        for player in players.iter_mut().filter(|p| p.team == team) {
+            let left = if player.id == player_id { bolts - BOLTS_FOR_ROBOT } else { 0 };
+            player.state.mode_or_default::<SiegeState>().bolts = left;
+        }
+
+        let info =
+            Spawn::new(String::from("Siege Robot"), ROBOT_HEALTH, ROBOT_DAMAGE, f32::INFINITY, 1.0);
+        self.robots.insert(team, PlayerSpawn::new(info));
+
+        Some(team)
+    }
+
+    /// Returns the team whose IKE turret is attacked by the siege robot of `team`, which
+    /// is the first enemy team whose turret is still standing.
+    fn robot_target(&self, team: TeamId, players: &Players) -> Option<TeamId> {
+        players
+            .teams()
+            .into_iter()
+            .find(|t| *t != team && self.ikes.get(t).is_some_and(|ike| !ike.is_destroyed()))
+    }
+
+    /// Returns the position of the siege robot of `team`, in front of the IKE turret it
+    /// attacks.
+    fn robot_position(&self, team: TeamId, players: &Players) -> Option<Position> {
+        let ike = self.ikes.get(&self.robot_target(team, players)?)?;
+        let arena = players.arena();
+
+        Some(arena.move_towards(ike.position, arena.center(), 1))
+    }
+
+    /// Makes the siege robot of `team` attack the IKE turret of the first enemy team
+    /// whose turret is still standing.
+    ///
+    /// The IKE turret fires back at the robot and the robot is removed once it is destroyed.
+    ///
+    /// Forwards any error that occurs due to the event dispatch.
+    async fn robot_attack(
+        &mut self,
+        team: TeamId,
+        players: &Players,
+        handler: &dyn GameHandler,
+    ) -> Result<()> {
+        let damage = if let Some(robot) = self.robots.get(&team) {
+            robot.info.damage
+        } else {
+            return Ok(());
+        };
+
+        let target = self.robot_target(team, players);
+        let (target, ike) = match target.and_then(|t| self.ikes.get_mut(&t).map(|ike| (t, ike))) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+
+        ike.damage(damage);
+
+        let event = GameEvent::IkeDamaged { team: target, health: ike.health };
+        let recipients: Vec<PlayerId> =
+            players.iter().filter(|p| p.team == team || p.team == target).map(|p| p.id).collect();
+        BattleGameMode::notify(&event, &recipients, players, handler).await?;
+
+        if let Some(robot) = self.robots.get_mut(&team) {
+            robot.damage(IKE_DAMAGE);
+
+            if robot.is_dead() {
+                self.robots.remove(&team);
+
+                let event = GameEvent::RobotDestroyed { team };
+                BattleGameMode::broadcast(&event, players, handler).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn possible_moves(&self, player_id: PlayerId, players: &Players) -> Vec<SiegeMove> {
+        let player = if let Some(player) = players.get(player_id) {
+            player
+        } else {
+            return Vec::new();
+        };
+
+        let mut moves: Vec<SiegeMove> = GeneralMove::possible_moves(player, players)
+            .into_iter()
+            .map(SiegeMove::General)
+            .collect();
+        moves.push(SiegeMove::CollectBolt);
+
+        let can_attack = player.can_attack();
+        // Only a super which deals damage can hurt a robot.
+        let can_super =
+            player.can_super() && player.brawler_state.brawler.info().ult.damage.is_some();
+
+        for team in players.teams() {
+            let robot = match self.robot_position(team, players) {
+                Some(robot) if team != player.team && self.robots.contains_key(&team) => robot,
+                _ => continue,
+            };
+
+            // The robot can only be hit from within range and in the line of sight.
+            let in_sight = players.arena().has_line_of_sight(&player.state.position, &robot);
+            let reaches = |is_ult| in_sight && player.in_range(&robot, is_ult);
+
+            if can_attack && reaches(false) {
+                moves.push(SiegeMove::AttackRobot(team));
+            }
+
+            if can_super && reaches(true) {
+                moves.push(SiegeMove::UltRobot(team));
+            }
+
+            if !reaches(false) && player.move_range() > 0 {
+                moves.push(SiegeMove::MoveToRobot(team));
+            }
+        }
+
+        moves
+    }
+
+    /// Returns the result if only one IKE turret or no IKE turret is left standing.
+    fn check_result(&self) -> Option<GameResult> {
+        let mut standing = self.ikes.iter().filter(|(_, ike)| !ike.is_destroyed());
+
+        match (standing.next(), standing.next()) {
+            (None, _) => Some(GameResult::Draw),
+            (Some((winner, _)), None) => {
+                let mut losers: Vec<TeamId> =
+                    self.ikes.keys().copied().filter(|t| t != winner).collect();
+                losers.sort();
+
+                Some(GameResult::Decisive { winner: *winner, losers })
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the result at timeout based on the health of the IKE turrets.
+    ///
+    /// Returns `None` if more than one IKE turret has the most health left.
+    fn check_time_up(&self) -> Option<GameResult> {
+        let mut scores: Vec<(TeamId, u32)> =
+            self.ikes.iter().map(|(team, ike)| (*team, ike.health)).collect();
+        scores.sort();
+
+        BattleGameMode::result_from_scores(&scores)
+    }
+
+    async fn handle_move(
+        &mut self,
+        user_move: &SiegeMove,
+        player_id: PlayerId,
+        players: &mut Players,
+        handler: &dyn GameHandler,
+        rng: &mut GameRng,
+    ) -> Result<()> {
+        match user_move {
+            SiegeMove::General(gm) => return gm.handle_move(player_id, players, handler).await,
+            SiegeMove::MoveToRobot(team) => {
+                return match self.robot_position(*team, players) {
+                    Some(robot) => {
+                        BattleGameMode::move_towards(player_id, robot, players, handler).await
+                    },
+                    None => Ok(()),
+                };
+            },
+            _ => {},
+        }
+
+        let first = if let Some(player) = players.get_mut(player_id) {
+            player
+        } else {
+            return Ok(());
+        };
+
+        match user_move {
+            SiegeMove::General(_) | SiegeMove::MoveToRobot(_) => {},
+            SiegeMove::CollectBolt => {
+                // 50% chance of collecting a bolt.
+                let new = *rng::select_one(&[0, 1], &[1, 1], rng).unwrap_or(&0);
+
+                first.state.mode_or_default::<SiegeState>().bolts += new;
+
+                let event = GameEvent::BoltsCollected { player: player_id, bolts: new };
+                BattleGameMode::notify(&event, &[player_id], players, handler).await?;
+            },
+            SiegeMove::AttackRobot(team) | SiegeMove::UltRobot(team) => {
+                // The robot is too big to miss, so all the projectiles hit.
+                let is_ult = matches!(user_move, SiegeMove::UltRobot(_));
+                let damage =
+                    BattleGameMode::damage_structure(player_id, players, is_ult, self.round_num);
+
+                if let Some(robot) = self.robots.get_mut(team) {
+                    robot.damage(damage);
+
+                    if robot.is_dead() {
+                        self.robots.remove(team);
+
+                        let event = GameEvent::RobotDestroyed { team: *team };
+                        BattleGameMode::broadcast(&event, players, handler).await?;
+                    }
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents a team's IKE turret in Siege.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct IkeTurret {
+    /// Maximum health points the IKE turret can have.
+    pub max_health: u32,
+    /// The IKE turret's current health points.
+    pub health: u32,
+    /// The position of the IKE turret in the arena.
+    pub position: Position,
+}
+
+impl IkeTurret {
+    /// Creates a new [`IkeTurret`] with given health at given position.
+    pub fn new(health: u32, position: Position) -> Self {
+        Self { max_health: health, health, position }
+    }
+
+    /// Applies `amount` damage to the IKE turret.
+    pub fn damage(&mut self, amount: u32) {
+        self.health = self.health.saturating_sub(amount);
+    }
+
+    /// Checks if the IKE turret is destroyed.
+    pub fn is_destroyed(&self) -> bool {
+        self.health == 0
+    }
+}
+
+/// Represents the state of a player in Siege.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct SiegeState {
+    /// The number of bolts the player is holding.
+    pub bolts: u32,
+}
+
+impl SiegeState {
+    /// Creates a new [`SiegeState`] with no bolts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Represents a user move in Siege.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum SiegeMove {
+    /// Represents a general move.
+    General(GeneralMove),
+    /// Represents the move to collect a bolt.
+    CollectBolt,
+    /// Represents the move towards the siege robot of the given team.
+    MoveToRobot(TeamId),
+    /// Represents the move to attack the siege robot of the given team using a normal attack.
+    AttackRobot(TeamId),
+    /// Represents the move to attack the siege robot of the given team using the super.
+    UltRobot(TeamId),
+}
+
+#[cfg(test)]
+mod test_siege {
+    use super::*;
+    use crate::gameplay::test_utils::players;
+
+    #[test]
+    fn test_summon_robot() {
+        let mut players = players();
+        let mut siege = Siege::new();
+        let give_bolts = |players: &mut Players, id, bolts| {
+            players.get_mut(PlayerId(id)).unwrap().state.mode_state = SiegeState { bolts }.into();
+        };
+
+        give_bolts(&mut players, 1, 2);
+        give_bolts(&mut players, 2, 3);
+        assert_eq!(siege.summon_robot(PlayerId(2), &mut players), None);
+
+        // The bolts left over are kept by the player who summons the robot.
+        give_bolts(&mut players, 3, 3);
+        assert_eq!(siege.summon_robot(PlayerId(3), &mut players), Some(TeamId(0)));
+        let bolts: Vec<u32> =
+            players.iter().map(|p| p.state.mode::<SiegeState>().map_or(0, |s| s.bolts)).collect();
+        assert_eq!(bolts, [0, 0, 2, 0, 0, 0]);
+
+        // A team can't summon another robot while its robot is standing.
+        give_bolts(&mut players, 1, BOLTS_FOR_ROBOT);
+        assert_eq!(siege.summon_robot(PlayerId(1), &mut players), None);
+    }
+}
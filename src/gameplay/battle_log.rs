@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::player::PlayerId;
+use super::player::{Player, PlayerId};
+use super::trophies::TrophyCalculator;
+use super::{GameResult, Players};
+use crate::model::game_mode::Event;
 
 /// A struct representing a battle log entry.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -20,6 +23,41 @@ impl BattleLogEntry {
     pub fn new(players: Vec<PlayerLogEntry>, game_mode: String) -> Self {
         Self { players, game_mode, timestamp: Utc::now() }
     }
+
+    /// Creates a new [`BattleLogEntry`] for a finished game.
+    ///
+    /// The trophy change of every player is calculated with `calculator`.
+    pub fn from_result(
+        event: Event,
+        result: &GameResult,
+        players: &Players,
+        calculator: &TrophyCalculator,
+    ) -> Self {
+        let deltas = calculator.deltas(result, event.get_event_type(), players);
+
+        let players = players
+            .iter()
+            .zip(deltas)
+            .map(|(player, (_, reward_trophies))| {
+                let placement = result.placement(player.team);
+                let won = result.winner() == Some(player.team);
+
+                let mut entry = PlayerLogEntry::new(
+                    player.id,
+                    PlayerBrawlerLogEntry::from_player(player),
+                    reward_trophies,
+                    won,
+                );
+                if let (true, Some(rank)) = (result.is_ranked() || result.is_forfeit(), placement) {
+                    entry.set_placement(rank, result.placements_len());
+                }
+
+                entry
+            })
+            .collect();
+
+        Self::new(players, event.to_string())
+    }
 }
 
 /// Represents a battle log entry for a player.
@@ -34,6 +72,8 @@ pub struct PlayerLogEntry {
     pub reward_trophies: i32,
     /// Whether the player won the battle.
     pub won: bool,
+    /// The placement of the player's team, if the battle ranks the teams.
+    pub placement: Option<Placement>,
 }
 
 impl PlayerLogEntry {
@@ -44,7 +84,29 @@ impl PlayerLogEntry {
         reward_trophies: i32,
         won: bool,
     ) -> Self {
-        Self { id, brawler_entry, reward_trophies, won }
+        Self { id, brawler_entry, reward_trophies, won, placement: None }
+    }
+
+    /// Sets the placement of the player's team in the battle.
+    pub fn set_placement(&mut self, rank: usize, total: usize) {
+        self.placement = Some(Placement::new(rank, total));
+    }
+}
+
+/// Represents the placement of a team in a ranked battle, such as Showdown.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Placement {
+    /// The rank of the team, starting from 1 for the winner.
+    pub rank: usize,
+    /// The total number of teams ranked in the battle.
+    pub total: usize,
+}
+
+impl Placement {
+    /// Creates a new [`Placement`].
+    pub fn new(rank: usize, total: usize) -> Self {
+        Self { rank, total }
     }
 }
 
@@ -65,4 +127,95 @@ impl PlayerBrawlerLogEntry {
     pub fn new(name: String, level: u32, trophies: u32) -> Self {
         Self { name, level, trophies }
     }
+
+    /// Creates a new [`PlayerBrawlerLogEntry`] from the Brawler of a player.
+    pub fn from_player(player: &Player) -> Self {
+        let state = &player.brawler_state;
+
+        Self::new(state.brawler.info().name.clone(), state.level, state.trophies)
+    }
+}
+
+#[cfg(test)]
+mod test_battle_log {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::error::Result;
+    use crate::gameplay::ai::{AiHandler, RandomStrategy};
+    use crate::gameplay::battle_game_mode::{BattleGameMode, GameModeExt};
+    use crate::gameplay::player::TeamId;
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::gameplay::{Game, GameHandler};
+    use crate::utils::rng::GameRng;
+
+    /// A custom game mode which ends with a fixed result.
+    #[derive(Debug)]
+    struct Fixed(GameResult);
+
+    #[async_trait]
+    impl GameModeExt for Fixed {
+        async fn run(
+            &self,
+            _players: &mut Players,
+            _handler: &dyn GameHandler,
+            _rng: &mut GameRng,
+        ) -> Result<GameResult> {
+            Ok(self.0.clone())
+        }
+    }
+
+    /// Returns a game of Heist between a team with 600 trophies and a team with none.
+    fn heist() -> Game {
+        let mut players = players();
+        for player in players.iter_mut().filter(|p| p.team == TeamId(0)) {
+            player.brawler_state.trophies = 600;
+        }
+
+        Game::new(BattleGameMode::new(Event::Heist), players, AiHandler::new(RandomStrategy, 7))
+    }
+
+    /// Returns the log entries of the players with ID 1 and 4, one from each team.
+    fn entries(log: &BattleLogEntry) -> (&PlayerLogEntry, &PlayerLogEntry) {
+        let entry = |id| log.players.iter().find(|p| p.id == PlayerId(id)).unwrap();
+        (entry(1), entry(4))
+    }
+
+    #[test]
+    fn test_from_result() {
+        let mut game = heist();
+        let result = GameResult::Decisive { winner: TeamId(1), losers: vec![TeamId(0)] };
+        game.registry.register(Event::Heist, Fixed(result));
+
+        let (result, log) = block_on(game.run_logged()).unwrap();
+        assert!(result.is_decisive());
+        assert_eq!(log.game_mode, Event::Heist.to_string());
+        assert_eq!(log.players.len(), 6);
+
+        let (first, second) = entries(&log);
+        let brawler = &first.brawler_entry;
+        assert_eq!((brawler.name.as_str(), brawler.level, brawler.trophies), ("Shelly", 9, 600));
+        assert_eq!((first.reward_trophies, first.won), (-7, false));
+        assert_eq!((second.reward_trophies, second.won), (8, true));
+        assert!(first.placement.is_none() && second.placement.is_none());
+
+        // The forfeited team is ranked last.
+        let game = heist();
+        game.cancel.forfeit(PlayerId(4));
+        let (result, log) = block_on(game.run_logged()).unwrap();
+        assert!(result.is_forfeit());
+
+        let (first, second) = entries(&log);
+        assert_eq!((first.reward_trophies, first.won), (8, true));
+        assert_eq!((second.reward_trophies, second.won), (0, false));
+        let placement = |entry: &PlayerLogEntry| entry.placement.map(|p| (p.rank, p.total));
+        assert_eq!((placement(first), placement(second)), (Some((1, 2)), Some((2, 2))));
+
+        // Nobody wins or loses trophies in a draw.
+        let mut game = heist();
+        game.registry.register(Event::Heist, Fixed(GameResult::Draw));
+        let (_, log) = block_on(game.run_logged()).unwrap();
+        assert!(log.players.iter().all(|p| !p.won && p.reward_trophies == 0));
+        assert!(log.players.iter().all(|p| p.placement.is_none()));
+    }
 }
@@ -0,0 +1,287 @@
+//! Events dispatched to the players during a game.
+
+use serde::{Deserialize, Serialize};
+
+use super::player::{Player, PlayerId, Position, TeamId};
+use super::GameResult;
+
+/// Represents something that happened in a game.
+///
+/// Events are delivered to the players through [`GameHandler::event`]. Each variant
+/// documents the players it is dispatched to.
+///
+/// [`GameHandler::event`]: super::GameHandler::event
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum GameEvent {
+    /// A player attacked another player.
+    ///
+    /// `damage` is the health lost by the target, `0` if the attack missed.
+    /// Dispatched to the attacker and the target.
+    Damaged { attacker: PlayerId, target: PlayerId, damage: u32 },
+    /// A player attacked the spawn of another player.
+    ///
+    /// Dispatched to the attacker and the owner of the spawn.
+    SpawnDamaged { attacker: PlayerId, owner: PlayerId, damage: u32 },
+    /// A player summoned a spawn with their super.
+    ///
+    /// Dispatched to all the players.
+    SpawnSummoned { owner: PlayerId, team: TeamId, spawn: String },
+    /// The spawn of a player attacked another player.
+    ///
+    /// Dispatched to the owner of the spawn and the target.
+    SpawnAttacked { owner: PlayerId, target: PlayerId, spawn: String, damage: u32 },
+    /// A player used their Gadget.
+    ///
+    /// Dispatched to all the players.
+    GadgetUsed { player: PlayerId, team: TeamId, gadget: String },
+    /// A player moved to a new position.
+    ///
+    /// Dispatched to the player.
+    Moved { player: PlayerId, position: Position },
+    /// A player is stunned and skips their turn.
+    ///
+    /// Dispatched to all the players.
+    Stunned { player: PlayerId, team: TeamId },
+    /// A player was defeated and respawns next round.
+    ///
+    /// `team` is the team of the defeated player. Dispatched to all the players.
+    Defeated { attacker: PlayerId, defeated: PlayerId, team: TeamId },
+    /// A player is respawning and skips their turn.
+    ///
+    /// Dispatched to the respawning player.
+    Respawning { player: PlayerId },
+    /// The poison damaged a player.
+    ///
+    /// Dispatched to the poisoned player.
+    PoisonTick { player: PlayerId, damage: u32 },
+    /// A team was eliminated from the game.
+    ///
+    /// Dispatched to all the players.
+    Eliminated { team: TeamId },
+    /// A player tried to collect gems.
+    ///
+    /// `gems` is the number of gems collected, which can be `0`. Dispatched to the player.
+    GemsCollected { player: PlayerId, gems: u32 },
+    /// A defeated player dropped some of their gems.
+    ///
+    /// Dispatched to the defeated player.
+    GemsDropped { player: PlayerId, gems: u32 },
+    /// A player tried to collect a power-up.
+    ///
+    /// `powerups` is the number of power-ups collected, which can be `0`.
+    /// Dispatched to the player.
+    PowerUpsCollected { player: PlayerId, powerups: u32 },
+    /// A player tried to collect a bolt.
+    ///
+    /// `bolts` is the number of bolts collected, which can be `0`. Dispatched to the player.
+    BoltsCollected { player: PlayerId, bolts: u32 },
+    /// A player earned stars for their team.
+    ///
+    /// Dispatched to all the players.
+    StarsEarned { player: PlayerId, team: TeamId, stars: u32 },
+    /// A player scored a goal for their team.
+    ///
+    /// Dispatched to all the players.
+    Goal { scorer: PlayerId, team: TeamId },
+    /// The scores are tied at the end of the regular time and the game goes into overtime.
+    ///
+    /// Dispatched to all the players.
+    Overtime,
+    /// The safe of a team was damaged.
+    ///
+    /// `health` is the health left in the safe. Dispatched to all the players.
+    SafeDamaged { team: TeamId, health: u32 },
+    /// A team summoned a siege robot.
+    ///
+    /// Dispatched to all the players.
+    RobotSummoned { team: TeamId },
+    /// The IKE turret of a team was hit by a siege robot.
+    ///
+    /// `health` is the health left in the IKE turret. Dispatched to the players of the
+    /// team and the team whose siege robot hit the IKE turret.
+    IkeDamaged { team: TeamId, health: u32 },
+    /// The siege robot of a team was destroyed.
+    ///
+    /// Dispatched to all the players.
+    RobotDestroyed { team: TeamId },
+    /// The time ran out before the game ended.
+    ///
+    /// Dispatched to all the players.
+    TimeOut,
+    /// The game ended with the result.
+    ///
+    /// Dispatched to all the players after the game mode returns.
+    GameOver(GameResult),
+}
+
+impl GameEvent {
+    /// Returns an English message describing the event from the point of view of `recipient`.
+    ///
+    /// This is the message shared by the default implementation of [`GameHandler::event`].
+    ///
+    /// [`GameHandler::event`]: super::GameHandler::event
+    pub fn message(&self, recipient: &Player) -> String {
+        let is_me = |id: &PlayerId| *id == recipient.id;
+        let is_ally = |team: &TeamId| *team == recipient.team;
+
+        match self {
+            Self::Damaged { attacker, damage: 0, .. } if is_me(attacker) => {
+                String::from("Your attack missed!")
+            },
+            Self::Damaged { damage: 0, .. } => String::from("An opponent's attack missed you!"),
+            Self::Damaged { attacker, damage, .. } if is_me(attacker) => {
+                format!("You dealt {} damage to an opponent!", damage)
+            },
+            Self::Damaged { damage, .. } => format!("An opponent dealt {} damage to you!", damage),
+            Self::SpawnDamaged { attacker, damage, .. } if is_me(attacker) => {
+                format!("You dealt {} damage to an enemy spawn!", damage)
+            },
+            Self::SpawnDamaged { damage, .. } => format!("Your spawn took {} damage!", damage),
+            Self::SpawnSummoned { owner, spawn, .. } if is_me(owner) => {
+                format!("You summoned {}!", spawn)
+            },
+            Self::SpawnSummoned { team, spawn, .. } if is_ally(team) => {
+                format!("Your teammate summoned {}!", spawn)
+            },
+            Self::SpawnSummoned { spawn, .. } => format!("Opponent summoned {}!", spawn),
+            Self::SpawnAttacked { owner, spawn, .. } if is_me(owner) => {
+                format!("Your {} attacked an opponent!", spawn)
+            },
+            Self::SpawnAttacked { spawn, .. } => format!("Enemy {} attacked you!", spawn),
+            Self::GadgetUsed { player, gadget, .. } if is_me(player) => {
+                format!("You used {}!", gadget)
+            },
+            Self::GadgetUsed { team, gadget, .. } if is_ally(team) => {
+                format!("Your teammate used {}!", gadget)
+            },
+            Self::GadgetUsed { gadget, .. } => format!("Opponent used {}!", gadget),
+            Self::Moved { position, .. } => {
+                format!("You moved to ({}, {}).", position.x(), position.y())
+            },
+            Self::Stunned { player, .. } if is_me(player) => String::from("You are stunned!"),
+            Self::Stunned { team, .. } if is_ally(team) => {
+                String::from("Your teammate is stunned!")
+            },
+            Self::Stunned { .. } => String::from("Opponent is stunned!"),
+            Self::Defeated { defeated, .. } if is_me(defeated) => {
+                String::from("You are defeated! Respawning next round.")
+            },
+            Self::Defeated { attacker, .. } if is_me(attacker) => {
+                String::from("Opponent defeated! Respawning next round.")
+            },
+            Self::Defeated { team, .. } if is_ally(team) => {
+                String::from("Your teammate is defeated! Respawning next round.")
+            },
+            Self::Defeated { .. } => String::from("Opponent defeated! Respawning next round."),
+            Self::Respawning { .. } => String::from("You are respawning!"),
+            Self::PoisonTick { damage, .. } => {
+                format!("The poison dealt {} damage to you!", damage)
+            },
+            Self::Eliminated { team } if is_ally(team) => String::from("Your team is eliminated!"),
+            Self::Eliminated { .. } => String::from("A team is eliminated!"),
+            Self::GemsCollected { gems: 0, .. } => String::from("You couldn't collect any gems."),
+            Self::GemsCollected { gems, .. } => format!("You collected {} gem(s)!", gems),
+            Self::GemsDropped { gems, .. } => format!("You dropped {} gem(s)!", gems),
+            Self::PowerUpsCollected { powerups: 0, .. } => {
+                String::from("You couldn't collect any power-ups.")
+            },
+            Self::PowerUpsCollected { powerups, .. } => {
+                format!("You collected {} power-up(s)!", powerups)
+            },
+            Self::BoltsCollected { bolts: 0, .. } => {
+                String::from("You couldn't collect any bolts.")
+            },
+            Self::BoltsCollected { bolts, .. } => format!("You collected {} bolt(s)!", bolts),
+            Self::StarsEarned { player, stars, .. } if is_me(player) => {
+                format!("You earned {} star(s)!", stars)
+            },
+            Self::StarsEarned { team, stars, .. } if is_ally(team) => {
+                format!("Your teammate earned {} star(s)!", stars)
+            },
+            Self::StarsEarned { stars, .. } => format!("Opponent earned {} star(s)!", stars),
+            Self::Goal { scorer, .. } if is_me(scorer) => String::from("Goal! You scored."),
+            Self::Goal { team, .. } if is_ally(team) => String::from("Goal! Your teammate scored."),
+            Self::Goal { .. } => String::from("Goal! Opponent scored."),
+            Self::Overtime => String::from("Scores are tied. Overtime!"),
+            Self::SafeDamaged { team, health } if is_ally(team) => {
+                format!("Your safe has {} health left.", health)
+            },
+            Self::SafeDamaged { health, .. } => format!("Enemy safe has {} health left.", health),
+            Self::RobotSummoned { team } if is_ally(team) => {
+                String::from("Your siege robot has been summoned!")
+            },
+            Self::RobotSummoned { .. } => String::from("Enemy siege robot has been summoned!"),
+            Self::IkeDamaged { team, health } if is_ally(team) => {
+                format!("Enemy siege robot hit your IKE! It has {} health left.", health)
+            },
+            Self::IkeDamaged { health, .. } => {
+                format!("Your siege robot hit the enemy IKE! It has {} health left.", health)
+            },
+            Self::RobotDestroyed { team } if is_ally(team) => {
+                String::from("Your siege robot was destroyed!")
+            },
+            Self::RobotDestroyed { .. } => String::from("Enemy siege robot was destroyed!"),
+            Self::TimeOut => String::from("Time's up!"),
+            Self::GameOver(result) => match result {
+                GameResult::Ranked(placements) => match result.placement(recipient.team) {
+                    Some(placement) => {
+                        format!("You placed #{} of {}.", placement, placements.len())
+                    },
+                    None => String::from("The match is over."),
+                },
+                GameResult::Decisive { winner, .. } if is_ally(winner) => {
+                    String::from("Your team won the match!")
+                },
+                GameResult::Decisive { .. } => String::from("Your team lost the match."),
+                GameResult::Draw => String::from("Match ended in a draw."),
+                GameResult::Forfeit { forfeited, .. } if is_ally(forfeited) => {
+                    String::from("Your team forfeited the match.")
+                },
+                GameResult::Forfeit { .. } => String::from("Opponent forfeited the match."),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_event {
+    use super::*;
+    use crate::gameplay::test_utils::players;
+
+    #[test]
+    fn test_message() {
+        let players = players();
+        let messages = |event: &GameEvent| {
+            [PlayerId(1), PlayerId(2), PlayerId(4)]
+                .map(|id| event.message(players.get(id).unwrap()))
+        };
+
+        let stunned = GameEvent::Stunned { player: PlayerId(1), team: TeamId(0) };
+        assert_eq!(messages(&stunned), [
+            "You are stunned!",
+            "Your teammate is stunned!",
+            "Opponent is stunned!"
+        ]);
+
+        // The attacker is told that they defeated an opponent.
+        let defeated =
+            GameEvent::Defeated { attacker: PlayerId(4), defeated: PlayerId(1), team: TeamId(0) };
+        assert_eq!(messages(&defeated), [
+            "You are defeated! Respawning next round.",
+            "Your teammate is defeated! Respawning next round.",
+            "Opponent defeated! Respawning next round."
+        ]);
+
+        let result = GameResult::Decisive { winner: TeamId(1), losers: vec![TeamId(0)] };
+        assert_eq!(messages(&GameEvent::GameOver(result)), [
+            "Your team lost the match.",
+            "Your team lost the match.",
+            "Your team won the match!"
+        ]);
+
+        let result = GameResult::Ranked(vec![TeamId(1), TeamId(0)]);
+        assert_eq!(messages(&GameEvent::GameOver(result))[0], "You placed #2 of 2.");
+        assert_eq!(messages(&GameEvent::GameOver(GameResult::Draw))[2], "Match ended in a draw.");
+    }
+}
@@ -1,20 +1,50 @@
-use std::collections::HashMap;
 use std::sync::Arc;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use super::battle_brawler::{BrawlerExt, Spawn};
+use super::battle_brawler::{
+    self,
+    BattleGadget,
+    BattleStarPower,
+    BrawlerExt,
+    GadgetSlot,
+    Spawn,
+    StarPowerSlot,
+};
+use super::battle_game_mode::bounty::BountyState;
+use super::battle_game_mode::brawlball::BrawlBallState;
+use super::battle_game_mode::gemgrab::GemGrabState;
+use super::battle_game_mode::hotzone::HotZoneState;
+use super::battle_game_mode::showdown::ShowdownState;
+use super::battle_game_mode::siege::SiegeState;
+use super::status::StatusEffects;
+use crate::error::{Error, Result};
+use crate::resource::bs_box::{UnlockedGadgets, UnlockedStarPowers};
+
+/// The speed a Brawler needs to move one tile in a turn.
+const SPEED_PER_TILE: u32 = 240;
 
 /// A unique identifier for a [`Player`] during a brawl.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct PlayerId(pub u64);
 
+/// A unique identifier for a team of [`Player`]s during a brawl.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub struct TeamId(pub u8);
+
 /// Represents a player during a brawl.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
 pub struct Player {
     /// The unique ID of the player.
     pub id: PlayerId,
+    /// The ID of the player's team.
+    ///
+    /// It is assigned when the player is added to [`Players`](crate::gameplay::Players).
+    pub team: TeamId,
     /// The state of the player.
     pub state: PlayerState,
     /// Whether the player is the first one in lobby or not.
@@ -25,12 +55,19 @@ pub struct Player {
 
 impl Player {
     /// Creates a new [`Player`](Player) with most values set to default.
+    ///
+    /// The player starts with all the charges of their equipped Gadget and with their
+    /// equipped Star Power active.
     pub fn new(id: PlayerId, brawler_state: PlayerBrawlerState, is_first: bool) -> Self {
         let info = brawler_state.brawler.info();
         let ammo = info.attack.max_ammo;
         let health = info.health;
 
-        Self { id, is_first, brawler_state, state: PlayerState::new(ammo, health) }
+        let mut state = PlayerState::new(ammo, health);
+        state.gadget_charges = brawler_state.gadget().map_or(0, |gadget| gadget.charges);
+        state.star_power = brawler_state.star_power_slot();
+
+        Self { id, team: TeamId(0), is_first, brawler_state, state }
     }
 
     /// Tries to regenerate the player's ammo.
@@ -53,8 +90,12 @@ impl Player {
 
     /// Sets the player's status as [`Respawning`] and health as max health.
     ///
+    /// The player's spawn and status effects, if any, are removed.
+    ///
     /// [`Respawning`]: CharacterStatus::Respawning
     pub fn respawn(&mut self) {
+        self.state.spawn = None;
+        self.state.effects.clear();
         self.state.status = CharacterStatus::Respawning;
         self.state.health = self.state.max_health;
     }
@@ -68,6 +109,42 @@ impl Player {
     pub fn can_super(&self) -> bool {
         self.state.attacks > self.brawler_state.brawler.super_hits_required()
     }
+
+    /// Checks if a position is within the range of the player's normal attack or super.
+    ///
+    /// A super without a range of its own reaches as far as the normal attack.
+    pub fn in_range(&self, position: &Position, is_ult: bool) -> bool {
+        let info = self.brawler_state.brawler.info();
+        let range =
+            if is_ult { info.ult.range.unwrap_or(info.attack.range) } else { info.attack.range };
+
+        self.state.position.distance(position) <= range
+    }
+
+    /// Checks if the player can use their Gadget.
+    pub fn can_use_gadget(&self) -> bool {
+        self.state.gadget_charges > 0 && self.brawler_state.gadget().is_some()
+    }
+
+    /// Returns the number of tiles the player can move in a turn.
+    ///
+    /// It depends on the speed of the player's Brawler, reduced by the slows applied to
+    /// the player. A player who isn't slowed completely can always move one tile.
+    pub fn move_range(&self) -> u32 {
+        let slow = u32::from(self.state.effects.slow());
+        let speed = self.brawler_state.brawler.info().speed * (100 - slow) / 100;
+
+        if slow < 100 {
+            (speed / SPEED_PER_TILE).max(1)
+        } else {
+            0
+        }
+    }
+
+    /// Checks if the player is on the same team as another player.
+    pub fn is_teammate(&self, other: &Player) -> bool {
+        self.team == other.team
+    }
 }
 
 impl PartialEq for Player {
@@ -77,7 +154,7 @@ impl PartialEq for Player {
 }
 
 /// A wrapper for the player's spawn.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PlayerSpawn {
     /// Stats and info about the spawn.
     pub info: Spawn,
@@ -85,20 +162,82 @@ pub struct PlayerSpawn {
     pub health: u32,
     /// The status of the spawn.
     pub status: CharacterStatus,
+    /// The player the spawn is going after, if any.
+    pub target: Option<PlayerId>,
+}
+
+impl PlayerSpawn {
+    /// Creates a new [`PlayerSpawn`] with full health from the spawn's info.
+    pub fn new(info: Spawn) -> Self {
+        let health = info.health;
+
+        Self { info, health, status: CharacterStatus::Alive, target: None }
+    }
+
+    /// Applies `amount` damage to the spawn, updating the status if the spawn dies.
+    pub fn damage(&mut self, amount: u32) {
+        if self.health <= amount {
+            self.health = 0;
+            self.status = CharacterStatus::Dead;
+        } else {
+            self.health -= amount;
+        }
+    }
+
+    /// Heals the spawn by given amount up till its max health.
+    pub fn heal(&mut self, amount: u32) {
+        self.health = self.info.health.min(self.health + amount);
+    }
+
+    /// Checks if the spawn is dead.
+    pub fn is_dead(&self) -> bool {
+        self.status.is_dead() || self.health == 0
+    }
 }
 
 /// A point representing the player's position.
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Position(u32, u32);
 
 impl Position {
     pub fn new(x: u32, y: u32) -> Self {
         Self(x, y)
     }
+
+    /// Returns distance from another position.
+    ///
+    /// Distance is calculated using the distance formula:
+    /// `sqrt((x1 - x2)^2 + (y1 - y2)^2)`
+    pub fn distance(&self, other: &Self) -> f32 {
+        ((self.0 as f32 - other.0 as f32).powi(2) + (self.1 as f32 - other.1 as f32).powi(2)).sqrt()
+    }
+
+    /// Returns the x coordinate of the position.
+    pub fn x(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the y coordinate of the position.
+    pub fn y(&self) -> u32 {
+        self.1
+    }
+
+    /// Returns the number of tiles to move to reach another position, moving along
+    /// both the axes at once.
+    pub fn tiles_from(&self, other: &Self) -> u32 {
+        self.0.abs_diff(other.0).max(self.1.abs_diff(other.1))
+    }
+
+    /// Returns the direction of a step towards another position along each axis.
+    pub fn direction(&self, other: &Self) -> (i64, i64) {
+        let step = |from: u32, to: u32| (i64::from(to) - i64::from(from)).signum();
+
+        (step(self.0, other.0), step(self.1, other.1))
+    }
 }
 
 /// The current state of a player.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[non_exhaustive]
 pub struct PlayerState {
     /// Amount of ammo the player has.
@@ -110,6 +249,8 @@ pub struct PlayerState {
     /// It is reset after a player uses his super.
     pub attacks: u32,
     /// Whether the player is invincible right now or not.
+    ///
+    /// A player who dodges is invincible till their next turn.
     pub is_invincibile: bool,
     /// The player's current status (alive, dead or respawning)
     pub status: CharacterStatus,
@@ -121,12 +262,18 @@ pub struct PlayerState {
     pub health: u32,
     /// Round number when last attacked opponent or got attacked by the opponent
     pub last_attack_round: u8,
-    /// Whether the player is stunned right now or not.
-    pub is_stunned: bool,
+    /// The status effects applied to the player.
+    pub effects: StatusEffects,
     /// The player's position on the map.
     pub position: Position,
-    /// Extra gamemode-specific data.
-    pub extra: HashMap<&'static str, u8>,
+    /// Number of times the player can still use their Gadget.
+    pub gadget_charges: u8,
+    /// The slot of the Star Power active for the player, if any.
+    pub star_power: Option<StarPowerSlot>,
+    /// The game mode specific state of the player.
+    ///
+    /// Use [`mode`](Self::mode) to read the state of a specific game mode.
+    pub mode_state: ModeState,
 }
 
 impl PlayerState {
@@ -142,9 +289,11 @@ impl PlayerState {
             max_health: health,
             health,
             last_attack_round: 0,
-            is_stunned: false,
+            effects: StatusEffects::new(),
             position: Position::new(0, 0),
-            extra: HashMap::new(),
+            gadget_charges: 0,
+            star_power: None,
+            mode_state: ModeState::None,
         }
     }
 
@@ -153,9 +302,7 @@ impl PlayerState {
     /// Distance is calculated using the distance formula:
     /// `sqrt((x1 - x2)^2 + (y1 - y2)^2)`
     pub fn distance_from_player(&self, player_state: &Self) -> f32 {
-        ((self.position.0 as f32 - player_state.position.0 as f32).powi(2)
-            + (self.position.1 as f32 - player_state.position.1 as f32).powi(2))
-        .sqrt()
+        self.position.distance(&player_state.position)
     }
 
     /// Tries to regenerate the player's ammo.
@@ -169,7 +316,10 @@ impl PlayerState {
     /// `true` is returned if ammo is regenerated, `false` if not.
     pub fn regenerate_ammo(&mut self, brawler: &dyn BrawlerExt, round_num: u8) -> bool {
         let attack = &brawler.info().attack;
-        let reload = attack.reload.ceil() as u8;
+        let reload = battle_brawler::with_star_power(self, attack.reload, |slot, reload| {
+            brawler.star_power_reload(slot, reload, self)
+        });
+        let reload = reload.ceil() as u8;
         let max_ammo = attack.max_ammo;
 
         if self.last_used_ammo <= round_num.saturating_sub(reload) && self.ammo < max_ammo {
@@ -181,8 +331,48 @@ impl PlayerState {
         }
     }
 
+    /// Returns the game mode specific state of type `T`, if the player has it.
+    pub fn mode<T: ModeStateExt>(&self) -> Option<&T> {
+        T::get(&self.mode_state)
+    }
+
+    /// Returns the game mode specific state of type `T`, if the player has it.
+    pub fn mode_mut<T: ModeStateExt>(&mut self) -> Option<&mut T> {
+        T::get_mut(&mut self.mode_state)
+    }
+
+    /// Returns the game mode specific state of type `T`, replacing the player's state
+    /// with the default state of type `T` if the player doesn't have it.
+    pub fn mode_or_default<T: ModeStateExt + Default>(&mut self) -> &mut T {
+        if T::get(&self.mode_state).is_none() {
+            self.mode_state = T::default().into();
+        }
+
+        T::get_mut(&mut self.mode_state).expect("mode state was just set")
+    }
+
+    /// Returns the state of a custom game mode as `T`.
+    ///
+    /// Returns `None` if the player has no custom state or it is not of type `T`.
+    pub fn custom_mode<T: DeserializeOwned>(&self) -> Option<T> {
+        match &self.mode_state {
+            ModeState::Custom(value) => T::deserialize(value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Sets the state of the player to the state of a custom game mode.
+    ///
+    /// Returns [`Error::MiscError`] if the state can't be serialized.
+    pub fn set_custom_mode<T: Serialize>(&mut self, state: &T) -> Result<()> {
+        let value = serde_json::to_value(state).map_err(|e| Error::MiscError(e.to_string()))?;
+        self.mode_state = ModeState::Custom(value);
+
+        Ok(())
+    }
+
     /// Heals the player by given amount up till the max health.
-    fn heal(&mut self, amount: u32) {
+    pub fn heal(&mut self, amount: u32) {
         self.health = self.max_health.min(self.health + amount);
     }
 
@@ -206,16 +396,103 @@ impl PlayerState {
     }
 
     /// Applies `amount` damage to the player, updating the status if the player dies.
+    ///
+    /// The damage is reduced by the player's shield, if any, and no damage is taken while
+    /// the player is dodging. The player's spawn, if any, is removed when the player dies.
     pub fn damage(&mut self, amount: u32) {
+        if self.is_invincibile {
+            return;
+        }
+
+        let amount = amount - amount * u32::from(self.effects.shield()) / 100;
+
         if self.health <= amount {
             self.health = 0;
             self.status = CharacterStatus::Dead;
+            self.spawn = None;
         } else {
             self.health -= amount;
         }
     }
 }
 
+/// The game mode specific state of a player.
+///
+/// Each game mode sets the state of the players when the game starts. Use
+/// [`PlayerState::mode`] to read the state of a specific game mode.
+///
+/// Custom game modes store their state as JSON with [`PlayerState::set_custom_mode`]
+/// and read it with [`PlayerState::custom_mode`], so that it is kept in snapshots
+/// and replays.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub enum ModeState {
+    /// The player has no game mode specific state.
+    #[default]
+    None,
+    /// The state of a player in Gem Grab.
+    GemGrab(GemGrabState),
+    /// The state of a player in Showdown.
+    Showdown(ShowdownState),
+    /// The state of a player in Brawl Ball.
+    BrawlBall(BrawlBallState),
+    /// The state of a player in Bounty.
+    Bounty(BountyState),
+    /// The state of a player in Siege.
+    Siege(SiegeState),
+    /// The state of a player in Hot Zone.
+    HotZone(HotZoneState),
+    /// The state of a player in a custom game mode.
+    Custom(serde_json::Value),
+}
+
+/// Extension trait for the game mode specific states stored in a [`ModeState`].
+pub trait ModeStateExt: Into<ModeState> {
+    /// Returns the state stored in `state`, if it is of this type.
+    fn get(state: &ModeState) -> Option<&Self>;
+
+    /// Returns the state stored in `state` mutably, if it is of this type.
+    fn get_mut(state: &mut ModeState) -> Option<&mut Self>;
+}
+
+/// Implements [`ModeStateExt`] and the conversion into [`ModeState`] for a state.
+macro_rules! impl_mode_state {
+    ($($state:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$state> for ModeState {
+                fn from(state: $state) -> Self {
+                    Self::$variant(state)
+                }
+            }
+
+            impl ModeStateExt for $state {
+                fn get(state: &ModeState) -> Option<&Self> {
+                    match state {
+                        ModeState::$variant(state) => Some(state),
+                        _ => None,
+                    }
+                }
+
+                fn get_mut(state: &mut ModeState) -> Option<&mut Self> {
+                    match state {
+                        ModeState::$variant(state) => Some(state),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_mode_state! {
+    GemGrabState => GemGrab,
+    ShowdownState => Showdown,
+    BrawlBallState => BrawlBall,
+    BountyState => Bounty,
+    SiegeState => Siege,
+    HotZoneState => HotZone,
+}
+
 /// Represents the state of a player's brawler.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -224,17 +501,82 @@ pub struct PlayerBrawlerState {
     pub brawler: Arc<dyn BrawlerExt>,
     /// The player's selected `Brawler`'s level.
     pub level: u32,
+    /// The player's selected `Brawler`'s trophies before the game.
+    pub trophies: u32,
+    /// The slot of the player's equipped Gadget, if any.
+    pub gadget: Option<GadgetSlot>,
+    /// The slot of the player's equipped Star Power, if any.
+    pub star_power: Option<StarPowerSlot>,
 }
 
 impl PlayerBrawlerState {
     /// Creates a new [`PlayerBrawlerState`] with provided brawler and level.
+    ///
+    /// The brawler has no trophies, use [`set_trophies`](Self::set_trophies) to change them.
     pub fn new<B: 'static + BrawlerExt>(brawler: B, level: u32) -> Self {
-        Self { brawler: Arc::new(brawler), level }
+        Self { brawler: Arc::new(brawler), level, trophies: 0, gadget: None, star_power: None }
+    }
+
+    /// Returns the player's equipped Gadget, if any.
+    pub fn gadget(&self) -> Option<&BattleGadget> {
+        self.brawler.info().gadget(self.gadget?)
+    }
+
+    /// Equips the Gadget in `slot` if it is in the player's `unlocked` Gadgets.
+    ///
+    /// `true` is returned if the Gadget is equipped, `false` if it is not unlocked or
+    /// the Brawler doesn't have it.
+    pub fn equip_gadget(&mut self, slot: GadgetSlot, unlocked: UnlockedGadgets) -> bool {
+        let is_unlocked = match slot {
+            GadgetSlot::First => unlocked.first(),
+            GadgetSlot::Second => unlocked.second(),
+        };
+
+        if !is_unlocked || self.brawler.info().gadget(slot).is_none() {
+            return false;
+        }
+
+        self.gadget = Some(slot);
+        true
+    }
+
+    /// Returns the player's equipped Star Power, if any.
+    pub fn star_power(&self) -> Option<&BattleStarPower> {
+        self.brawler.info().star_power(self.star_power?)
+    }
+
+    /// Returns the slot of the player's equipped Star Power, if the Brawler has it.
+    fn star_power_slot(&self) -> Option<StarPowerSlot> {
+        self.star_power.filter(|slot| self.brawler.info().star_power(*slot).is_some())
+    }
+
+    /// Equips the Star Power in `slot` if it is in the player's `unlocked` Star Powers.
+    ///
+    /// `true` is returned if the Star Power is equipped, `false` if it is not unlocked
+    /// or the Brawler doesn't have it.
+    pub fn equip_star_power(&mut self, slot: StarPowerSlot, unlocked: UnlockedStarPowers) -> bool {
+        let is_unlocked = match slot {
+            StarPowerSlot::First => unlocked.first(),
+            StarPowerSlot::Second => unlocked.second(),
+        };
+
+        if !is_unlocked || self.brawler.info().star_power(slot).is_none() {
+            return false;
+        }
+
+        self.star_power = Some(slot);
+        true
+    }
+
+    /// Sets the trophies of the `Brawler` to `trophies`.
+    pub fn set_trophies(&mut self, trophies: u32) -> &mut Self {
+        self.trophies = trophies;
+        self
     }
 }
 
 /// A character's current status.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum CharacterStatus {
     /// Character is alive.
     Alive,
@@ -260,3 +602,112 @@ impl CharacterStatus {
         matches!(self, CharacterStatus::Dead)
     }
 }
+
+#[cfg(test)]
+mod test_player {
+    use super::*;
+    use crate::gameplay::battle_brawler::defaults::{Nita, Shelly};
+    use crate::gameplay::battle_brawler::{BattleGadget, BattleStarPower};
+    use crate::gameplay::test_utils::{nita_info, shelly_info};
+    use crate::gameplay::BattleGameMode;
+
+    #[test]
+    fn test_mode_state() {
+        let mut state = PlayerState::new(3, 3600);
+        assert!(state.mode::<GemGrabState>().is_none());
+
+        state.mode_or_default::<GemGrabState>().gems += 300;
+        assert_eq!(state.mode::<GemGrabState>().map(|s| s.gems), Some(300));
+
+        let json = serde_json::to_string(&state).unwrap();
+        let state: PlayerState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.mode::<GemGrabState>().map(|s| s.gems), Some(300));
+        assert!(state.mode::<BountyState>().is_none());
+    }
+
+    #[test]
+    fn test_dodge() {
+        let mut state = PlayerState::new(3, 3600);
+        state.is_invincibile = true;
+        state.damage(1000);
+        assert_eq!(state.health, 3600);
+
+        state.is_invincibile = false;
+        state.damage(1000);
+        assert_eq!(state.health, 2600);
+    }
+
+    #[test]
+    fn test_equip_gadget() {
+        let mut data = nita_info();
+        data.gadget1 = Some(BattleGadget {
+            name: String::from("Bear Hug"),
+            description: String::from("Heals Nita and Bruce."),
+            charges: 3,
+        });
+
+        let mut brawler = PlayerBrawlerState::new(Nita { data }, 9);
+        let mut unlocked = UnlockedGadgets::default();
+        assert!(!brawler.equip_gadget(GadgetSlot::First, unlocked));
+
+        unlocked.set_first(true).set_second(true);
+        assert!(!brawler.equip_gadget(GadgetSlot::Second, unlocked));
+        assert!(brawler.equip_gadget(GadgetSlot::First, unlocked));
+
+        let mut player = Player::new(PlayerId(1), brawler, true);
+        assert_eq!(player.state.gadget_charges, 3);
+        assert!(player.can_use_gadget());
+
+        player.state.damage(1000);
+        player.brawler_state.brawler.gadget(GadgetSlot::First, &mut player.state, 9);
+        assert_eq!(player.state.health, 3300);
+    }
+
+    #[test]
+    fn test_summon_spawn() {
+        let nita = Nita { data: nita_info() };
+        let (mut first, mut second) = (PlayerState::new(3, 3800), PlayerState::new(3, 3600));
+        nita.ult(&mut first, &mut second, 9);
+        assert_eq!(first.spawn.map(|bear| bear.info.name), Some(String::from("Bruce")));
+
+        // Nita summons nothing if the info of her super has no spawn.
+        let mut data = nita_info();
+        data.ult.spawn = None;
+        let nita = Nita { data };
+        let mut first = PlayerState::new(3, 3800);
+        assert!(!nita.has_spawn());
+        nita.ult(&mut first, &mut second, 9);
+        assert!(first.spawn.is_none());
+    }
+
+    #[test]
+    fn test_star_power() {
+        let mut data = shelly_info();
+        data.sp2 = Some(BattleStarPower {
+            name: String::from("Band-Aid"),
+            description: String::from("Heals Shelly faster."),
+        });
+
+        let mut brawler = PlayerBrawlerState::new(Shelly { data }, 1);
+        let mut unlocked = UnlockedStarPowers::default();
+        unlocked.set_first(true).set_second(true);
+        assert!(!brawler.equip_star_power(StarPowerSlot::First, unlocked));
+        assert!(brawler.equip_star_power(StarPowerSlot::Second, unlocked));
+
+        let mut player = Player::new(PlayerId(1), brawler, true);
+        assert_eq!(player.state.star_power, Some(StarPowerSlot::Second));
+
+        player.state.damage(3000);
+        assert!(BattleGameMode::heal(&mut player, 10));
+        assert_eq!(player.state.health, 900);
+
+        assert!(BattleGameMode::heal(&mut player, 10));
+        assert_eq!(player.state.health, 1200);
+
+        assert!(BattleGameMode::heal(&mut player, 10));
+        assert_eq!(player.state.health, 1500);
+
+        assert!(BattleGameMode::heal(&mut player, 10));
+        assert_eq!(player.state.health, 1600);
+    }
+}
@@ -0,0 +1,252 @@
+//! Recording and playback of games.
+//!
+//! A [`Replay`] holds everything needed to play a game again: the event, the seed of
+//! the random number generator, the roster and every move chosen by the players.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::arena::Arena;
+use super::battle_brawler::BrawlerRegistry;
+use super::player::{Player, PlayerId};
+use super::snapshot::{PlayerSnapshot, SnapshotHandle};
+use super::{GameEvent, GameHandler, GameModeRegistry, GameResult, Moves, Players};
+use crate::error::{Error, Result};
+use crate::model::game_mode::Event;
+use crate::utils::rng;
+
+/// Represents the record of a finished game.
+///
+/// Use [`Game::run_recorded`](super::Game::run_recorded) to record a game.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Replay {
+    /// The event of the game.
+    pub event: Event,
+    /// The seed of the random number generator used to run the game.
+    pub seed: u64,
+    /// The players of the game, as they were at the start of the game.
+    pub players: Vec<PlayerSnapshot>,
+    /// The moves chosen by the players, in the order they were made.
+    pub moves: Vec<RecordedMove>,
+    /// The result of the game.
+    pub result: GameResult,
+    /// The arena of the game.
+    #[serde(default)]
+    pub arena: Arena,
+}
+
+impl Replay {
+    /// Creates a new [`Replay`].
+    pub fn new(
+        event: Event,
+        seed: u64,
+        players: Vec<PlayerSnapshot>,
+        moves: Vec<RecordedMove>,
+        result: GameResult,
+    ) -> Self {
+        Self { event, seed, players, moves, result, arena: Arena::default() }
+    }
+
+    /// Restores the players at the start of the game with the Brawlers in `brawlers`,
+    /// on the arena of the game.
+    ///
+    /// Returns [`Error::UnregisteredBrawler`] if the Brawler of a player is not registered.
+    pub fn players(&self, brawlers: &BrawlerRegistry) -> Result<Players> {
+        let players = self.players.iter().map(|p| p.restore(brawlers)).collect::<Result<_>>()?;
+
+        Ok(Players { players, arena: self.arena.clone() })
+    }
+
+    /// Plays the recorded game again with the Brawlers in `brawlers` and the game
+    /// modes in `registry`.
+    ///
+    /// The messages dispatched during the playback are discarded. If the game ended
+    /// with a forfeit, the forfeiting team forfeits at the first prompt after the
+    /// recorded moves, which is where a player who went AFK or a game forfeited through
+    /// its [`CancelToken`](super::CancelToken) at a prompt stopped. A game forfeited
+    /// through the token at an event may not be reproduced.
+    ///
+    /// Returns [`Error::ReplayMismatch`] if the playback doesn't reach the recorded
+    /// result with the recorded moves. Forwards any error that occurs while restoring
+    /// the players or running the game.
+    pub async fn play(
+        &self,
+        brawlers: &BrawlerRegistry,
+        registry: &GameModeRegistry,
+    ) -> Result<GameResult> {
+        let mut players = self.players(brawlers)?;
+
+        let mut handler = ReplayHandler::new(self.moves.clone());
+        if let GameResult::Forfeit { forfeited, .. } = &self.result {
+            if let Some(player) = self.players.iter().find(|p| p.team == *forfeited) {
+                handler.set_forfeit(player.id);
+            }
+        }
+
+        let mut rng = rng::seeded(self.seed);
+
+        let result = registry.run(self.event, &mut players, &handler, &mut rng).await?;
+
+        if handler.remaining() > 0 {
+            return Err(Error::ReplayMismatch(format!(
+                "game ended with {} recorded moves left",
+                handler.remaining()
+            )));
+        }
+
+        if result != self.result {
+            return Err(Error::ReplayMismatch(format!(
+                "expected {:?}, got {:?}",
+                self.result, result
+            )));
+        }
+
+        Ok(result)
+    }
+}
+
+/// Represents a move chosen by a player in a recorded game.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct RecordedMove {
+    /// The ID of the player who chose the move.
+    pub player: PlayerId,
+    /// The index of the chosen move among the possible moves.
+    pub index: usize,
+}
+
+impl RecordedMove {
+    /// Creates a new [`RecordedMove`].
+    pub fn new(player: PlayerId, index: usize) -> Self {
+        Self { player, index }
+    }
+}
+
+/// A [`GameHandler`] which records the moves chosen through another handler.
+///
+/// The moves are added to the snapshots of the game as they are chosen.
+pub(crate) struct Recorder<'a> {
+    /// The handler which is recorded.
+    handler: &'a dyn GameHandler,
+    /// The snapshots the moves are added to.
+    snapshot: &'a SnapshotHandle,
+}
+
+impl<'a> Recorder<'a> {
+    /// Creates a new [`Recorder`] for `handler` which adds the moves to `snapshot`.
+    pub(crate) fn new(handler: &'a dyn GameHandler, snapshot: &'a SnapshotHandle) -> Self {
+        Self { handler, snapshot }
+    }
+}
+
+#[async_trait]
+impl GameHandler for Recorder<'_> {
+    async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()> {
+        self.handler.info(player_id, msg).await
+    }
+
+    async fn event(&self, player: &Player, event: &GameEvent) -> Result<()> {
+        self.handler.event(player, event).await
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        moves: Moves<'a>,
+        player: &Player,
+        players: &Players,
+    ) -> Result<usize> {
+        let index = self.handler.get_move_idx(moves, player, players).await?;
+
+        self.snapshot.push(RecordedMove::new(player.id, index));
+
+        Ok(index)
+    }
+}
+
+/// A [`GameHandler`] which chooses the recorded moves of a [`Replay`].
+///
+/// All the messages and events dispatched to it are discarded.
+#[derive(Debug)]
+pub struct ReplayHandler {
+    /// The recorded moves which have not been chosen yet.
+    moves: Mutex<VecDeque<RecordedMove>>,
+    /// The player whose team forfeits once all the moves have been chosen, if any.
+    forfeit: Option<PlayerId>,
+}
+
+impl ReplayHandler {
+    /// Creates a new [`ReplayHandler`] which chooses `moves` in order.
+    pub fn new(moves: Vec<RecordedMove>) -> Self {
+        Self { moves: Mutex::new(moves.into()), forfeit: None }
+    }
+
+    /// Makes the team of the player with given ID forfeit the game at the first prompt
+    /// after all the moves have been chosen, like a player who goes AFK.
+    pub fn set_forfeit(&mut self, player_id: PlayerId) -> &mut Self {
+        self.forfeit = Some(player_id);
+        self
+    }
+
+    /// Returns the number of recorded moves which have not been chosen yet.
+    pub fn remaining(&self) -> usize {
+        self.moves.lock().map_or(0, |moves| moves.len())
+    }
+}
+
+#[async_trait]
+impl GameHandler for ReplayHandler {
+    async fn info(&self, _player_id: &PlayerId, _msg: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        _moves: Moves<'a>,
+        player: &Player,
+        _players: &Players,
+    ) -> Result<usize> {
+        let next = self.moves.lock().ok().and_then(|mut moves| moves.pop_front());
+
+        match next {
+            Some(m) if m.player == player.id => Ok(m.index),
+            Some(m) => Err(Error::ReplayMismatch(format!(
+                "expected a move from player {}, got a prompt for player {}",
+                m.player.0, player.id.0
+            ))),
+            None => match self.forfeit {
+                Some(id) => Err(Error::AfkForfeit(id)),
+                None => Err(Error::ReplayMismatch(String::from("no recorded moves left"))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_replay {
+    use super::*;
+    use crate::gameplay::ai::{AiHandler, RandomStrategy};
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::gameplay::{BattleGameMode, Game};
+
+    #[test]
+    fn test_play() {
+        for event in [Event::GemGrab, Event::BrawlBall, Event::Heist, Event::Siege] {
+            let handler = AiHandler::new(RandomStrategy, 7);
+            let mut game = Game::new(BattleGameMode::new(event), players(), handler);
+            game.seed = 42;
+
+            let (result, replay) = block_on(game.run_recorded()).unwrap();
+            let (brawlers, registry) = (BrawlerRegistry::default(), GameModeRegistry::default());
+
+            assert_eq!(result, block_on(replay.play(&brawlers, &registry)).unwrap());
+
+            let mut other = replay.clone();
+            other.moves.pop();
+            assert!(block_on(other.play(&brawlers, &registry)).is_err());
+        }
+    }
+}
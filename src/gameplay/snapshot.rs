@@ -0,0 +1,302 @@
+//! Snapshots of running games.
+//!
+//! A [`Snapshot`] holds everything needed to resume a game, for example, after the bot
+//! restarts: the event, the seed of the random number generator, the players at the
+//! start of the game and every move chosen so far. A resumed game plays the recorded
+//! moves again before the players are prompted for new moves.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::arena::Arena;
+use super::battle_brawler::{BrawlerInfo, BrawlerRegistry, GadgetSlot, StarPowerSlot};
+use super::player::{Player, PlayerBrawlerState, PlayerId, PlayerState, TeamId};
+use super::replay::{RecordedMove, ReplayHandler};
+use super::{GameEvent, GameHandler, Moves, Players};
+use crate::error::Result;
+use crate::model::game_mode::Event;
+
+/// Represents the state of a running game.
+///
+/// Use the [`snapshot`](super::Game::snapshot) handle of a game to take snapshots and
+/// [`Game::resume`](super::Game::resume) to resume a game from a snapshot.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Snapshot {
+    /// The event of the game.
+    pub event: Event,
+    /// The seed of the random number generator used to run the game.
+    pub seed: u64,
+    /// The players of the game, as they were at the start of the game.
+    pub players: Vec<PlayerSnapshot>,
+    /// The moves chosen by the players so far, in the order they were made.
+    pub moves: Vec<RecordedMove>,
+    /// The arena of the game.
+    #[serde(default)]
+    pub arena: Arena,
+}
+
+impl Snapshot {
+    /// Creates a new [`Snapshot`].
+    pub fn new(
+        event: Event,
+        seed: u64,
+        players: Vec<PlayerSnapshot>,
+        moves: Vec<RecordedMove>,
+    ) -> Self {
+        Self { event, seed, players, moves, arena: Arena::default() }
+    }
+
+    /// Restores the players at the start of the game with the Brawlers in `brawlers`,
+    /// on the arena of the game.
+    ///
+    /// Returns [`Error::UnregisteredBrawler`] if the Brawler of a player is not registered.
+    ///
+    /// [`Error::UnregisteredBrawler`]: crate::error::Error::UnregisteredBrawler
+    pub fn players(&self, brawlers: &BrawlerRegistry) -> Result<Players> {
+        let players = self.players.iter().map(|p| p.restore(brawlers)).collect::<Result<_>>()?;
+
+        Ok(Players { players, arena: self.arena.clone() })
+    }
+}
+
+/// Represents a player in a [`Snapshot`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct PlayerSnapshot {
+    /// The ID of the player.
+    pub id: PlayerId,
+    /// The ID of the player's team.
+    pub team: TeamId,
+    /// Whether the player is the first one in lobby or not.
+    pub is_first: bool,
+    /// The info of the player's Brawler.
+    pub brawler: BrawlerInfo,
+    /// The level of the player's Brawler.
+    pub level: u32,
+    /// The trophies of the player's Brawler before the game.
+    pub trophies: u32,
+    /// The slot of the player's equipped Gadget, if any.
+    pub gadget: Option<GadgetSlot>,
+    /// The slot of the player's equipped Star Power, if any.
+    pub star_power: Option<StarPowerSlot>,
+    /// The state of the player.
+    pub state: PlayerState,
+}
+
+impl PlayerSnapshot {
+    /// Creates a new [`PlayerSnapshot`] from a player.
+    pub fn new(player: &Player) -> Self {
+        let brawler_state = &player.brawler_state;
+
+        Self {
+            id: player.id,
+            team: player.team,
+            is_first: player.is_first,
+            brawler: brawler_state.brawler.info().clone(),
+            level: brawler_state.level,
+            trophies: brawler_state.trophies,
+            gadget: brawler_state.gadget,
+            star_power: brawler_state.star_power,
+            state: player.state.clone(),
+        }
+    }
+
+    /// Restores the player with the Brawler implementation registered in `brawlers`.
+    ///
+    /// Returns [`Error::UnregisteredBrawler`] if the player's Brawler is not registered.
+    ///
+    /// [`Error::UnregisteredBrawler`]: crate::error::Error::UnregisteredBrawler
+    pub fn restore(&self, brawlers: &BrawlerRegistry) -> Result<Player> {
+        let brawler_state = PlayerBrawlerState {
+            brawler: brawlers.build(self.brawler.clone())?,
+            level: self.level,
+            trophies: self.trophies,
+            gadget: self.gadget,
+            star_power: self.star_power,
+        };
+
+        Ok(Player {
+            id: self.id,
+            team: self.team,
+            state: self.state.clone(),
+            is_first: self.is_first,
+            brawler_state,
+        })
+    }
+}
+
+/// A handle to take [`Snapshot`]s of a running [`Game`](super::Game).
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotHandle(Arc<Mutex<Option<Snapshot>>>);
+
+impl SnapshotHandle {
+    /// Creates a new [`SnapshotHandle`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a snapshot of the game.
+    ///
+    /// Returns `None` if the game hasn't started yet.
+    pub fn get(&self) -> Option<Snapshot> {
+        self.0.lock().ok().and_then(|snapshot| snapshot.clone())
+    }
+
+    /// Starts taking snapshots of a game from `snapshot`.
+    pub(crate) fn start(&self, snapshot: Snapshot) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = Some(snapshot);
+        }
+    }
+
+    /// Adds a move chosen by a player to the snapshot.
+    pub(crate) fn push(&self, recorded: RecordedMove) {
+        if let Ok(mut snapshot) = self.0.lock() {
+            if let Some(snapshot) = snapshot.as_mut() {
+                snapshot.moves.push(recorded);
+            }
+        }
+    }
+}
+
+/// A [`GameHandler`] which chooses the moves of a [`Snapshot`] before forwarding
+/// the prompts to another handler.
+///
+/// The messages and events dispatched before the first prompt after the moves of the
+/// snapshot are discarded, since the players may have received them already. Only
+/// the result of a game which ends before that prompt is delivered.
+pub(crate) struct Resume<'a> {
+    /// The moves chosen before the snapshot was taken.
+    replay: ReplayHandler,
+    /// The handler used once all the moves have been chosen again.
+    handler: &'a dyn GameHandler,
+    /// Whether a player has been prompted through `handler` yet.
+    live: AtomicBool,
+}
+
+impl<'a> Resume<'a> {
+    /// Creates a new [`Resume`] which chooses `moves` before forwarding to `handler`.
+    pub(crate) fn new(moves: Vec<RecordedMove>, handler: &'a dyn GameHandler) -> Self {
+        let live = AtomicBool::new(moves.is_empty());
+
+        Self { replay: ReplayHandler::new(moves), handler, live }
+    }
+
+    /// Returns the number of moves which have not been chosen again yet.
+    pub(crate) fn remaining(&self) -> usize {
+        self.replay.remaining()
+    }
+
+    /// Checks if the messages are delivered through the handler.
+    fn is_live(&self) -> bool {
+        self.live.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl GameHandler for Resume<'_> {
+    async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()> {
+        if !self.is_live() {
+            return Ok(());
+        }
+
+        self.handler.info(player_id, msg).await
+    }
+
+    async fn event(&self, player: &Player, event: &GameEvent) -> Result<()> {
+        if !self.is_live() && !matches!(event, GameEvent::GameOver(_)) {
+            return Ok(());
+        }
+
+        self.handler.event(player, event).await
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        moves: Moves<'a>,
+        player: &Player,
+        players: &Players,
+    ) -> Result<usize> {
+        if self.remaining() > 0 {
+            return self.replay.get_move_idx(moves, player, players).await;
+        }
+
+        // The events of the last move chosen again have been discarded too.
+        self.live.store(true, Ordering::SeqCst);
+        self.handler.get_move_idx(moves, player, players).await
+    }
+}
+
+#[cfg(test)]
+mod test_snapshot {
+    use super::*;
+    use crate::gameplay::ai::{AiHandler, RandomStrategy};
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::gameplay::{BattleGameMode, Game, GameModeRegistry};
+
+    /// A handler which logs the messages and prompts of the players before forwarding
+    /// them to another handler.
+    struct LogHandler<H>(H, Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl<H: GameHandler> GameHandler for LogHandler<H> {
+        async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()> {
+            self.1.lock().unwrap().push(format!("{:?}: {}", player_id, msg));
+            Ok(())
+        }
+
+        async fn get_move_idx<'a>(
+            &self,
+            moves: Moves<'a>,
+            player: &Player,
+            players: &Players,
+        ) -> Result<usize> {
+            self.1.lock().unwrap().push(format!("{:?}: prompt", player.id));
+            self.0.get_move_idx(moves, player, players).await
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let handler = LogHandler(AiHandler::new(RandomStrategy, 7), Arc::clone(&log));
+        let mut game = Game::new(BattleGameMode::new(Event::BrawlBall), players(), handler);
+        game.seed = 42;
+
+        let (result, replay) = block_on(game.run_recorded()).unwrap();
+        let (before, after) = replay.moves.split_at(replay.moves.len() / 2);
+
+        let roster = players().iter().map(PlayerSnapshot::new).collect();
+        let snapshot = Snapshot::new(Event::BrawlBall, 42, roster, before.to_vec());
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+
+        let resumed_log = Arc::new(Mutex::new(Vec::new()));
+        let handler = LogHandler(ReplayHandler::new(after.to_vec()), Arc::clone(&resumed_log));
+        let brawlers = BrawlerRegistry::default();
+        let game = Game::resume(snapshot, &brawlers, GameModeRegistry::default(), handler).unwrap();
+        let handle = game.snapshot.clone();
+
+        assert_eq!(result, block_on(game.run()).unwrap());
+        assert_eq!(replay.moves, handle.get().unwrap().moves);
+
+        // The players only receive the messages from the first prompt after the snapshot.
+        let log = log.lock().unwrap();
+        let (first_live, _) = log
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| msg.ends_with(": prompt"))
+            .nth(before.len())
+            .unwrap();
+        assert_eq!(log[first_live..], resumed_log.lock().unwrap()[..]);
+
+        let handler = ReplayHandler::new(Vec::new());
+        let registry = GameModeRegistry::default();
+        assert!(Game::resume(handle.get().unwrap(), &BrawlerRegistry::new(), registry, handler)
+            .is_err());
+    }
+}
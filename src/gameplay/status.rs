@@ -0,0 +1,222 @@
+//! Status effects applied to players during a game.
+//!
+//! Effects last for a number of turns of the affected player and are ticked at the
+//! start of each of their turns by [`BattleGameMode::start_turn`]. Damage over time is
+//! dealt at the start of the turns of the player who applied it instead, so that the
+//! player is credited for the players it defeats.
+//!
+//! [`BattleGameMode::start_turn`]: super::BattleGameMode::start_turn
+
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use super::player::PlayerId;
+
+/// Represents a status effect.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum StatusEffect {
+    /// The player skips their turns.
+    Stun,
+    /// The speed of the player is reduced by `percent`.
+    Slow { percent: u8 },
+    /// The damage taken by the player is reduced by `percent`.
+    Shield { percent: u8 },
+    /// The player takes `damage` at the start of their turns.
+    ///
+    /// It is used for damage dealt by the map, like the poison clouds of Showdown.
+    Poison { damage: u32 },
+    /// The player takes `damage` from `source` at the start of the turns of `source`.
+    DamageOverTime { damage: u32, source: PlayerId },
+}
+
+impl StatusEffect {
+    /// Checks if the effect stacks with other effects of the same kind.
+    ///
+    /// Poison and damage over time stack, every other effect is refreshed instead.
+    pub fn stacks(&self) -> bool {
+        matches!(self, Self::Poison { .. } | Self::DamageOverTime { .. })
+    }
+
+    /// Checks if the effect is of the same kind as another effect.
+    pub fn is_same_kind(&self, other: &Self) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    /// Returns the strength of the effect, used to compare effects which don't stack.
+    fn strength(&self) -> u32 {
+        match *self {
+            Self::Stun => 0,
+            Self::Slow { percent } | Self::Shield { percent } => percent.into(),
+            Self::Poison { damage } | Self::DamageOverTime { damage, .. } => damage,
+        }
+    }
+}
+
+/// Represents a status effect applied to a player.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ActiveEffect {
+    /// The effect.
+    pub effect: StatusEffect,
+    /// The number of turns of the player the effect lasts for.
+    pub turns: u8,
+}
+
+impl ActiveEffect {
+    /// Creates a new [`ActiveEffect`].
+    pub fn new(effect: StatusEffect, turns: u8) -> Self {
+        Self { effect, turns }
+    }
+}
+
+/// The status effects applied to a player.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StatusEffects(Vec<ActiveEffect>);
+
+impl StatusEffects {
+    /// Creates a new [`StatusEffects`] with no effects.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies an effect for given number of turns.
+    ///
+    /// Effects which [stack](StatusEffect::stacks) are added alongside the existing
+    /// effects. Otherwise, the stronger of the new and the existing effect of the same
+    /// kind is kept and lasts for the longer of their durations.
+    pub fn apply(&mut self, effect: StatusEffect, turns: u8) {
+        if turns == 0 {
+            return;
+        }
+
+        let existing = self.0.iter_mut().find(|e| e.effect.is_same_kind(&effect));
+        match existing {
+            Some(existing) if !effect.stacks() => {
+                if effect.strength() >= existing.effect.strength() {
+                    existing.effect = effect;
+                }
+
+                existing.turns = existing.turns.max(turns);
+            },
+            _ => self.0.push(ActiveEffect::new(effect, turns)),
+        }
+    }
+
+    /// Removes all the effects of the same kind as `effect`.
+    pub fn remove(&mut self, effect: &StatusEffect) {
+        self.0.retain(|e| !e.effect.is_same_kind(effect));
+    }
+
+    /// Removes all the effects.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Returns an iterator over the effects.
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveEffect> {
+        self.0.iter()
+    }
+
+    /// Checks if there are no effects.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks if the player is stunned.
+    pub fn is_stunned(&self) -> bool {
+        self.0.iter().any(|e| e.effect == StatusEffect::Stun)
+    }
+
+    /// Returns the percentage by which the speed of the player is reduced.
+    pub fn slow(&self) -> u8 {
+        self.percent(|effect| match effect {
+            StatusEffect::Slow { percent } => Some(*percent),
+            _ => None,
+        })
+    }
+
+    /// Returns the percentage by which the damage taken by the player is reduced.
+    pub fn shield(&self) -> u8 {
+        self.percent(|effect| match effect {
+            StatusEffect::Shield { percent } => Some(*percent),
+            _ => None,
+        })
+    }
+
+    /// Returns the highest percentage of the effects matched by `percent`, up to 100.
+    fn percent(&self, percent: impl Fn(&StatusEffect) -> Option<u8>) -> u8 {
+        self.0.iter().filter_map(|e| percent(&e.effect)).max().unwrap_or(0).min(100)
+    }
+
+    /// Ticks the effects of the player at the start of their turn.
+    ///
+    /// The duration of every effect except damage over time is reduced by one turn and
+    /// the expired effects are removed. Returns the poison damage the player takes.
+    pub fn tick(&mut self) -> u32 {
+        let mut damage = 0;
+
+        for active in self.0.iter_mut() {
+            match active.effect {
+                StatusEffect::DamageOverTime { .. } => continue,
+                StatusEffect::Poison { damage: poison } => damage += poison,
+                _ => {},
+            }
+
+            active.turns -= 1;
+        }
+
+        self.0.retain(|e| e.turns > 0);
+
+        damage
+    }
+
+    /// Ticks the damage over time applied by `source` at the start of their turn.
+    ///
+    /// The duration of the damage over time is reduced by one turn and the expired
+    /// effects are removed. Returns the damage the player takes.
+    pub fn tick_damage_over_time(&mut self, source: PlayerId) -> u32 {
+        let mut damage = 0;
+
+        for active in self.0.iter_mut() {
+            if let StatusEffect::DamageOverTime { damage: dot, source: s } = active.effect {
+                if s == source {
+                    damage += dot;
+                    active.turns -= 1;
+                }
+            }
+        }
+
+        self.0.retain(|e| e.turns > 0);
+
+        damage
+    }
+}
+
+#[cfg(test)]
+mod test_status {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut effects = StatusEffects::new();
+        effects.apply(StatusEffect::Slow { percent: 30 }, 1);
+        effects.apply(StatusEffect::Slow { percent: 20 }, 3);
+        effects.apply(StatusEffect::Poison { damage: 100 }, 2);
+        effects.apply(StatusEffect::Poison { damage: 100 }, 1);
+        effects.apply(StatusEffect::DamageOverTime { damage: 50, source: PlayerId(2) }, 1);
+
+        assert_eq!(effects.slow(), 30);
+        assert_eq!(effects.iter().count(), 4);
+
+        assert_eq!(effects.tick(), 200);
+        assert_eq!(effects.tick(), 100);
+        assert_eq!(effects.tick(), 0);
+        assert_eq!(effects.slow(), 0);
+
+        assert_eq!(effects.tick_damage_over_time(PlayerId(1)), 0);
+        assert_eq!(effects.tick_damage_over_time(PlayerId(2)), 50);
+        assert!(effects.is_empty());
+    }
+}
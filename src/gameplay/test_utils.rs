@@ -0,0 +1,96 @@
+//! Fixtures shared by the tests of the gameplay modules.
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use super::battle_brawler::defaults::Shelly;
+use super::battle_brawler::BrawlerInfo;
+use super::player::{Player, PlayerBrawlerState, PlayerId};
+use super::Players;
+
+/// Polls a future which never waits to completion.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Returns the info of Shelly.
+pub(crate) fn shelly_info() -> BrawlerInfo {
+    serde_json::from_str(
+        r#"{
+            "name": "Shelly",
+            "health": 3600,
+            "speed": 720,
+            "attack": {
+                "damage": 300,
+                "description": "Buckshot",
+                "range": 7.67,
+                "reload": 1.5,
+                "projectiles": 5
+            },
+            "ult": {
+                "damage": 320,
+                "description": "Super Shell",
+                "range": 7.67,
+                "projectiles": 9,
+                "hits_required": 3,
+                "spawn": null
+            }
+        }"#,
+    )
+    .unwrap()
+}
+
+/// Returns the info of Nita, whose super summons Bruce instead of dealing damage.
+pub(crate) fn nita_info() -> BrawlerInfo {
+    serde_json::from_str(
+        r#"{
+            "name": "Nita",
+            "health": 3800,
+            "speed": 720,
+            "attack": {
+                "damage": 920,
+                "description": "Rupture",
+                "range": 5.5,
+                "reload": 1.1,
+                "projectiles": 1
+            },
+            "ult": {
+                "damage": null,
+                "description": "Overbearing",
+                "range": null,
+                "projectiles": 0,
+                "hits_required": 3,
+                "spawn": {
+                    "name": "Bruce",
+                    "health": 4000,
+                    "damage": 800,
+                    "range": 1.5,
+                    "speed": 1.0
+                }
+            }
+        }"#,
+    )
+    .unwrap()
+}
+
+/// Returns a player with a level 9 Shelly.
+pub(crate) fn shelly(id: u64, is_first: bool) -> Player {
+    let brawler = PlayerBrawlerState::new(Shelly { data: shelly_info() }, 9);
+    Player::new(PlayerId(id), brawler, is_first)
+}
+
+/// Returns two teams of three level 9 Shellys.
+pub(crate) fn players() -> Players {
+    Players::from_teams(vec![vec![shelly(1, true), shelly(2, false), shelly(3, false)], vec![
+        shelly(4, false),
+        shelly(5, false),
+        shelly(6, false),
+    ]])
+}
@@ -0,0 +1,222 @@
+//! Deadlines for the moves of the players.
+//!
+//! The library doesn't depend on an async runtime, so the delays are provided by a
+//! [`Timer`], which is usually a thin wrapper around the runtime's sleep function.
+
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
+use std::sync::Mutex;
+use std::task::Poll;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::ai::{self, Strategy};
+use super::battle_game_mode::GeneralMove;
+use super::player::{Player, PlayerId};
+use super::{GameEvent, GameHandler, Moves, Players};
+use crate::error::{Error, Result};
+use crate::utils::rng::{self, GameRng};
+
+/// A source of delays used to enforce the deadlines.
+pub trait Timer: Send + Sync {
+    /// Returns a future which completes after `duration`.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+}
+
+/// The move chosen for a player who doesn't respond before the deadline.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Fallback {
+    /// Dodge, or choose the first move if dodging is not possible.
+    Dodge,
+    /// Choose the move with a [`Strategy`].
+    Strategy(Box<dyn Strategy>),
+}
+
+/// A [`GameHandler`] which enforces a deadline on the moves chosen through another handler.
+///
+/// If a player doesn't choose a move before the deadline, the [`Fallback`] move is
+/// chosen for them. A player who misses too many turns in a row forfeits the game
+/// with [`Error::AfkForfeit`].
+pub struct TimeoutHandler<H, T> {
+    /// The handler whose moves are timed.
+    handler: H,
+    /// The timer used for the deadlines.
+    timer: T,
+    /// The time a player has to choose a move.
+    deadline: Duration,
+    /// The move chosen when a player misses the deadline.
+    fallback: Fallback,
+    /// The number of turns in a row a player can miss before forfeiting, if any.
+    forfeit_after: Option<u32>,
+    /// The number of turns in a row each player has missed.
+    missed: Mutex<HashMap<PlayerId, u32>>,
+    /// The random number generator used by the fallback strategy.
+    rng: Mutex<GameRng>,
+}
+
+impl<H: GameHandler, T: Timer> TimeoutHandler<H, T> {
+    /// Creates a new [`TimeoutHandler`] which gives the players `deadline` to choose a move.
+    ///
+    /// `seed` is the seed of the random number generator used by the fallback strategy.
+    /// Players who miss the deadline dodge and never forfeit by default.
+    pub fn new(handler: H, timer: T, deadline: Duration, seed: u64) -> Self {
+        Self {
+            handler,
+            timer,
+            deadline,
+            fallback: Fallback::Dodge,
+            forfeit_after: None,
+            missed: Mutex::new(HashMap::new()),
+            rng: Mutex::new(rng::seeded(seed)),
+        }
+    }
+
+    /// Sets the move chosen when a player misses the deadline.
+    pub fn set_fallback(&mut self, fallback: Fallback) -> &mut Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Makes a player forfeit once they miss `turns` turns in a row.
+    pub fn set_forfeit_after(&mut self, turns: u32) -> &mut Self {
+        self.forfeit_after = Some(turns);
+        self
+    }
+
+    /// Returns the number of turns in a row the player with given ID has missed.
+    pub fn missed_turns(&self, player_id: &PlayerId) -> u32 {
+        self.missed.lock().map_or(0, |missed| missed.get(player_id).copied().unwrap_or(0))
+    }
+
+    /// Records a missed turn of the player and returns the number of turns in a row
+    /// they have missed.
+    fn miss(&self, player_id: PlayerId) -> u32 {
+        let mut missed = self.missed.lock().unwrap_or_else(|e| e.into_inner());
+        let count = missed.entry(player_id).or_insert(0);
+        *count += 1;
+
+        *count
+    }
+
+    /// Returns the index of the fallback move.
+    fn fallback_idx(&self, moves: Moves<'_>, player: &Player, players: &Players) -> usize {
+        match &self.fallback {
+            Fallback::Dodge => (0..moves.len())
+                .find(|idx| matches!(ai::general_move(moves, *idx), Some(GeneralMove::Dodge)))
+                .unwrap_or(0),
+            Fallback::Strategy(strategy) => {
+                let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+
+                strategy.choose(moves, player, players, &mut rng)
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<H: GameHandler, T: Timer> GameHandler for TimeoutHandler<H, T> {
+    async fn info(&self, player_id: &PlayerId, msg: &str) -> Result<()> {
+        self.handler.info(player_id, msg).await
+    }
+
+    async fn event(&self, player: &Player, event: &GameEvent) -> Result<()> {
+        self.handler.event(player, event).await
+    }
+
+    async fn get_move_idx<'a>(
+        &self,
+        moves: Moves<'a>,
+        player: &Player,
+        players: &Players,
+    ) -> Result<usize> {
+        let mut response = pin!(self.handler.get_move_idx(moves, player, players));
+        let mut timeout = self.timer.sleep(self.deadline);
+
+        let response = poll_fn(|cx| {
+            if let Poll::Ready(res) = response.as_mut().poll(cx) {
+                Poll::Ready(Some(res))
+            } else if timeout.as_mut().poll(cx).is_ready() {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        if let Some(res) = response {
+            if let Ok(mut missed) = self.missed.lock() {
+                missed.remove(&player.id);
+            }
+
+            return res;
+        }
+
+        let missed = self.miss(player.id);
+        if self.forfeit_after.is_some_and(|turns| missed >= turns) {
+            return Err(Error::AfkForfeit(player.id));
+        }
+
+        Ok(self.fallback_idx(moves, player, players))
+    }
+}
+
+#[cfg(test)]
+mod test_timeout {
+    use std::future::{pending, ready};
+
+    use super::*;
+    use crate::gameplay::player::TeamId;
+    use crate::gameplay::test_utils::{block_on, players};
+    use crate::gameplay::{BattleGameMode, BrawlerRegistry, Game, GameModeRegistry, GameResult};
+    use crate::model::game_mode::Event;
+
+    /// A timer whose delays are over immediately.
+    struct InstantTimer;
+
+    impl Timer for InstantTimer {
+        fn sleep(&self, _duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+            Box::pin(ready(()))
+        }
+    }
+
+    /// A handler which never chooses a move.
+    struct AfkHandler;
+
+    #[async_trait]
+    impl GameHandler for AfkHandler {
+        async fn info(&self, _player_id: &PlayerId, _msg: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_move_idx<'a>(
+            &self,
+            _moves: Moves<'a>,
+            _player: &Player,
+            _players: &Players,
+        ) -> Result<usize> {
+            pending().await
+        }
+    }
+
+    #[test]
+    fn test_timeout() {
+        let handler = TimeoutHandler::new(AfkHandler, InstantTimer, Duration::from_secs(30), 7);
+        let game = Game::new(BattleGameMode::new(Event::GemGrab), players(), handler);
+        assert!(block_on(game.run()).unwrap().is_draw());
+
+        let mut handler = TimeoutHandler::new(AfkHandler, InstantTimer, Duration::from_secs(30), 7);
+        handler.set_forfeit_after(3);
+        let game = Game::new(BattleGameMode::new(Event::GemGrab), players(), handler);
+        let (result, replay) = block_on(game.run_recorded()).unwrap();
+        assert!(matches!(result, GameResult::Forfeit { forfeited: TeamId(0), .. }));
+        assert_eq!(result.winner(), Some(TeamId(1)));
+
+        // The replay forfeits where the player went AFK.
+        let registry = GameModeRegistry::default();
+        let played = block_on(replay.play(&BrawlerRegistry::default(), &registry)).unwrap();
+        assert_eq!(played, result);
+    }
+}
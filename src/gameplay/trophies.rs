@@ -0,0 +1,269 @@
+//! Helpers to calculate the trophies won or lost in a battle.
+
+use serde::{Deserialize, Serialize};
+
+use super::player::PlayerId;
+use super::{GameResult, Players};
+use crate::model::game_mode::EventType;
+
+/// The trophy changes of a [`TrophyBracket`] indexed by the placement of a team.
+///
+/// The first entry is the trophy change for the first place, the second entry is
+/// for the second place and so on. Placements beyond the end of the table get
+/// the last entry.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlacementTrophies(pub Vec<i32>);
+
+impl PlacementTrophies {
+    /// Creates a new [`PlacementTrophies`] table from trophy changes by placement.
+    pub fn new(deltas: Vec<i32>) -> Self {
+        Self(deltas)
+    }
+
+    /// Returns the trophy change for given placement, starting from 1 for the winner.
+    ///
+    /// Returns `0` if the table is empty.
+    pub fn delta(&self, placement: usize) -> i32 {
+        let idx = placement.saturating_sub(1).min(self.0.len().saturating_sub(1));
+
+        self.0.get(idx).copied().unwrap_or(0)
+    }
+}
+
+/// A [`PlacementTrophies`] table used for Brawlers with at least `min_trophies` trophies.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct TrophyBracket {
+    /// The trophies a Brawler needs to be in the bracket.
+    pub min_trophies: u32,
+    /// The trophy changes by placement for the bracket.
+    pub placements: PlacementTrophies,
+}
+
+impl TrophyBracket {
+    /// Creates a new [`TrophyBracket`] from minimum trophies and trophy changes by placement.
+    pub fn new(min_trophies: u32, deltas: Vec<i32>) -> Self {
+        Self { min_trophies, placements: PlacementTrophies::new(deltas) }
+    }
+}
+
+/// A table of trophy changes bracketed by the trophies of the Brawler.
+///
+/// Brawlers with more trophies gain less and lose more trophies, like in the game's
+/// trophy table. A Brawler uses the bracket with the highest minimum trophies not
+/// above their trophies.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TrophyTable(pub Vec<TrophyBracket>);
+
+impl TrophyTable {
+    /// Creates a new [`TrophyTable`] from brackets.
+    pub fn new(brackets: Vec<TrophyBracket>) -> Self {
+        Self(brackets)
+    }
+
+    /// Creates a table from `(min_trophies, deltas)` pairs.
+    fn from_rows(rows: &[(u32, &[i32])]) -> Self {
+        Self::new(
+            rows.iter().map(|(min, deltas)| TrophyBracket::new(*min, deltas.to_vec())).collect(),
+        )
+    }
+
+    /// Creates the table used for 3v3 events.
+    ///
+    /// The winners are placed first and the losers second.
+    pub fn three_vs_three() -> Self {
+        Self::from_rows(&[
+            (0, &[8, 0]),
+            (50, &[8, -1]),
+            (100, &[8, -2]),
+            (200, &[8, -3]),
+            (300, &[8, -4]),
+            (400, &[8, -5]),
+            (500, &[8, -6]),
+            (600, &[8, -7]),
+            (700, &[8, -8]),
+            (800, &[7, -9]),
+            (900, &[6, -10]),
+            (1000, &[5, -11]),
+            (1100, &[4, -12]),
+            (1200, &[3, -12]),
+        ])
+    }
+
+    /// Creates the table used for Solo Showdown with 10 players.
+    pub fn solo_showdown() -> Self {
+        Self::from_rows(&[
+            (0, &[10, 8, 7, 6, 4, 2, 2, 1, 0, 0]),
+            (50, &[10, 8, 7, 6, 3, 2, 2, 0, -1, -2]),
+            (100, &[10, 8, 7, 6, 3, 1, 0, -1, -2, -2]),
+            (200, &[10, 8, 6, 5, 3, 1, 0, -2, -3, -3]),
+            (300, &[10, 8, 6, 5, 2, 0, 0, -3, -4, -4]),
+            (400, &[10, 8, 6, 5, 2, -1, -2, -3, -5, -5]),
+            (500, &[10, 8, 6, 4, 2, -1, -2, -5, -6, -6]),
+            (600, &[10, 8, 6, 4, 1, -2, -2, -5, -7, -8]),
+            (700, &[10, 8, 6, 4, 1, -3, -4, -5, -8, -9]),
+            (800, &[9, 7, 5, 2, 0, -3, -4, -7, -9, -10]),
+            (900, &[8, 6, 4, 1, -1, -3, -6, -8, -10, -11]),
+            (1000, &[6, 5, 3, 1, -2, -5, -6, -9, -11, -12]),
+            (1100, &[5, 4, 1, 0, -2, -6, -7, -10, -12, -13]),
+            (1200, &[5, 3, 0, -1, -2, -6, -8, -11, -13, -14]),
+        ])
+    }
+
+    /// Creates the table used for Duo Showdown with 5 teams.
+    pub fn duo_showdown() -> Self {
+        Self::from_rows(&[
+            (0, &[9, 7, 4, 0, -1]),
+            (50, &[9, 7, 4, -1, -2]),
+            (100, &[9, 7, 3, -2, -2]),
+            (200, &[9, 7, 3, -3, -3]),
+            (300, &[9, 7, 2, -4, -4]),
+            (400, &[9, 6, 1, -5, -5]),
+            (500, &[9, 6, 1, -6, -6]),
+            (600, &[9, 6, 0, -7, -8]),
+            (700, &[9, 5, -1, -8, -9]),
+            (800, &[7, 4, -1, -9, -10]),
+            (900, &[7, 3, -2, -11, -12]),
+            (1000, &[6, 2, -3, -11, -12]),
+            (1100, &[5, 1, -4, -11, -12]),
+            (1200, &[4, 0, -5, -11, -12]),
+        ])
+    }
+
+    /// Returns the bracket of a Brawler with given trophies.
+    ///
+    /// Returns `None` if the trophies are below the minimum trophies of every bracket.
+    pub fn bracket(&self, trophies: u32) -> Option<&TrophyBracket> {
+        self.0.iter().filter(|b| b.min_trophies <= trophies).max_by_key(|b| b.min_trophies)
+    }
+
+    /// Returns the trophy change of a Brawler with given trophies for given placement.
+    ///
+    /// The Brawler never loses more trophies than it has. Returns `0` if the Brawler
+    /// is in no bracket.
+    pub fn delta(&self, trophies: u32, placement: usize) -> i32 {
+        let delta = self.bracket(trophies).map_or(0, |b| b.placements.delta(placement));
+
+        delta.max(-i32::try_from(trophies).unwrap_or(i32::MAX))
+    }
+}
+
+/// Calculates the trophies won or lost by the players in a battle.
+///
+/// The trophy tables used for every kind of event can be configured.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct TrophyCalculator {
+    /// The table used for team events.
+    pub team: TrophyTable,
+    /// The table used for individual events when every player is in their own team.
+    pub solo: TrophyTable,
+    /// The table used for individual events when the players play in teams.
+    pub duo: TrophyTable,
+}
+
+impl TrophyCalculator {
+    /// Creates a new [`TrophyCalculator`] from trophy tables.
+    pub fn new(team: TrophyTable, solo: TrophyTable, duo: TrophyTable) -> Self {
+        Self { team, solo, duo }
+    }
+
+    /// Returns the table used for an event of `event_type` with given players.
+    pub fn table(&self, event_type: EventType, players: &Players) -> &TrophyTable {
+        match event_type {
+            EventType::Team => &self.team,
+            EventType::Individual if players.teams().len() == players.len() => &self.solo,
+            EventType::Individual => &self.duo,
+        }
+    }
+
+    /// Returns the trophy change of every player for given result.
+    ///
+    /// The change depends on the placement of the player's team and the trophies of
+    /// the player's Brawler. Players whose team has no placement in the result, as in
+    /// a draw, get `0` trophies.
+    pub fn deltas(
+        &self,
+        result: &GameResult,
+        event_type: EventType,
+        players: &Players,
+    ) -> Vec<(PlayerId, i32)> {
+        let table = self.table(event_type, players);
+
+        players
+            .iter()
+            .map(|p| {
+                let delta = result
+                    .placement(p.team)
+                    .map_or(0, |placement| table.delta(p.brawler_state.trophies, placement));
+                (p.id, delta)
+            })
+            .collect()
+    }
+}
+
+impl Default for TrophyCalculator {
+    fn default() -> Self {
+        Self::new(
+            TrophyTable::three_vs_three(),
+            TrophyTable::solo_showdown(),
+            TrophyTable::duo_showdown(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_trophies {
+    use super::*;
+    use crate::gameplay::battle_game_mode::BattleGameMode;
+    use crate::gameplay::player::TeamId;
+    use crate::gameplay::test_utils::shelly;
+
+    #[test]
+    fn test_placement_delta() {
+        let table = PlacementTrophies::new(vec![10, 8, 7, 6, 4, 2, 2, 1, 0, 0]);
+        assert_eq!(10, table.delta(1));
+        assert_eq!(7, table.delta(3));
+        assert_eq!(0, table.delta(10));
+        assert_eq!(0, table.delta(11));
+        assert_eq!(10, table.delta(0));
+        assert_eq!(0, PlacementTrophies::new(Vec::new()).delta(1));
+    }
+
+    #[test]
+    fn test_bracket_delta() {
+        let table = TrophyTable::three_vs_three();
+        assert_eq!(8, table.delta(0, 1));
+        assert_eq!(0, table.delta(0, 2));
+        assert_eq!(-1, table.delta(75, 2));
+        assert_eq!(5, table.delta(1050, 1));
+        assert_eq!(-12, table.delta(5000, 2));
+
+        let table = TrophyTable::new(vec![TrophyBracket::new(100, vec![8, -5])]);
+        assert_eq!(0, table.delta(50, 1));
+        assert_eq!(-5, table.delta(150, 2));
+        assert_eq!(-3, TrophyTable::new(vec![TrophyBracket::new(0, vec![8, -5])]).delta(3, 2));
+    }
+
+    #[test]
+    fn test_forfeit_deltas() {
+        let players = Players::from_teams((1..=4).map(|id| vec![shelly(id, id == 1)]).collect());
+        let calculator = TrophyCalculator::default();
+
+        // The third team was eliminated before the second team forfeited.
+        let placements = [TeamId(3), TeamId(0), TeamId(1), TeamId(2)];
+        let result = BattleGameMode::forfeit(PlayerId(2), &placements, &players).unwrap();
+        assert_eq!(result.winner(), Some(TeamId(3)));
+        assert_eq!(calculator.deltas(&result, EventType::Individual, &players), vec![
+            (PlayerId(1), 8),
+            (PlayerId(2), 7),
+            (PlayerId(3), 6),
+            (PlayerId(4), 10)
+        ]);
+
+        // Without placements, the forfeited team is placed last.
+        let result = BattleGameMode::forfeit(PlayerId(2), &[], &players).unwrap();
+        assert_eq!(result.placement(TeamId(1)), Some(4));
+        assert_eq!(result.placement(TeamId(3)), Some(3));
+    }
+}
@@ -8,4 +8,5 @@
 
 pub mod brawler;
 pub mod game_mode;
+pub mod map;
 pub mod trophy_road;
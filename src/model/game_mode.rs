@@ -32,7 +32,7 @@ impl GameMode {
 /// Represents the game mode event.
 ///
 /// It includes 7 main game mode events present in Brawl Stars.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[non_exhaustive]
 pub enum Event {
     /// Represents Gem Grab.
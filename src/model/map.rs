@@ -0,0 +1,316 @@
+//! A collection of models and helpers related to maps.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Represents a tile of a [`Map`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum Tile {
+    /// Represents open ground.
+    #[default]
+    Empty,
+    /// Represents a wall, which blocks movement and line of sight.
+    Wall,
+    /// Represents a bush, which hides the players standing in it.
+    Bush,
+    /// Represents water, which blocks movement but not line of sight.
+    Water,
+    /// Represents the gem mine of Gem Grab.
+    #[serde(rename = "Gem Mine")]
+    GemMine,
+    /// Represents a safe of Heist, which blocks movement and line of sight.
+    Safe,
+    /// Represents a goal of Brawl Ball.
+    Goal,
+}
+
+impl Tile {
+    /// Returns the character representing the tile in a text layout.
+    pub const fn symbol(&self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Wall => '#',
+            Self::Bush => '*',
+            Self::Water => '~',
+            Self::GemMine => 'M',
+            Self::Safe => 'S',
+            Self::Goal => 'G',
+        }
+    }
+
+    /// Returns the tile represented by a character in a text layout.
+    pub const fn from_symbol(symbol: char) -> Option<Self> {
+        Some(match symbol {
+            '.' => Self::Empty,
+            '#' => Self::Wall,
+            '*' => Self::Bush,
+            '~' => Self::Water,
+            'M' => Self::GemMine,
+            'S' => Self::Safe,
+            'G' => Self::Goal,
+            _ => return None,
+        })
+    }
+
+    /// Checks if the players can move onto the tile.
+    pub const fn is_walkable(&self) -> bool {
+        !matches!(self, Self::Wall | Self::Water | Self::Safe)
+    }
+
+    /// Checks if the tile blocks the line of sight, and with it the attacks.
+    pub const fn blocks_sight(&self) -> bool {
+        matches!(self, Self::Wall | Self::Safe)
+    }
+}
+
+/// Represents a map, a grid of [`Tile`]s.
+///
+/// A map is loaded from a text layout with [`from_ascii`](Self::from_ascii), where every
+/// line is a row of tiles. The first line is the row at `y = 0`. The tiles are
+/// represented by these characters:
+///
+/// | Character | Tile               |
+/// |-----------|--------------------|
+/// | `.`       | [`Tile::Empty`]    |
+/// | `#`       | [`Tile::Wall`]     |
+/// | `*`       | [`Tile::Bush`]     |
+/// | `~`       | [`Tile::Water`]    |
+/// | `M`       | [`Tile::GemMine`]  |
+/// | `S`       | [`Tile::Safe`]     |
+/// | `G`       | [`Tile::Goal`]     |
+/// | `0`-`9`   | The spawn point of the team with that index, on open ground. |
+///
+/// In JSON, a map is an object with the `name` of the map and its `layout` as a list
+/// of lines.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "MapLayout", into = "MapLayout")]
+#[non_exhaustive]
+pub struct Map {
+    /// The name of the map.
+    pub name: String,
+    /// Number of tiles in a row.
+    width: u32,
+    /// Number of rows.
+    height: u32,
+    /// The tiles, row by row.
+    tiles: Vec<Tile>,
+    /// The spawn points of the teams, by their index.
+    spawns: BTreeMap<u8, (u32, u32)>,
+}
+
+impl Map {
+    /// Creates a new [`Map`] from a text layout.
+    ///
+    /// Blank lines and the whitespace around the lines are ignored. Returns
+    /// [`Error::InvalidMap`] if the layout is empty, has rows of different lengths,
+    /// unknown characters or more than one spawn point for a team.
+    pub fn from_ascii(name: impl Into<String>, layout: &str) -> Result<Self> {
+        let rows: Vec<&str> = layout.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(Error::InvalidMap(String::from("the layout is empty")));
+        }
+
+        let mut tiles = Vec::with_capacity(width * rows.len());
+        let mut spawns = BTreeMap::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(Error::InvalidMap(format!("row {} is not {} tiles long", y, width)));
+            }
+
+            for (x, symbol) in row.chars().enumerate() {
+                if let Some(team) = symbol.to_digit(10) {
+                    if spawns.insert(team as u8, (x as u32, y as u32)).is_some() {
+                        return Err(Error::InvalidMap(format!("team {} has two spawns", team)));
+                    }
+
+                    tiles.push(Tile::Empty);
+                    continue;
+                }
+
+                match Tile::from_symbol(symbol) {
+                    Some(tile) => tiles.push(tile),
+                    None => {
+                        return Err(Error::InvalidMap(format!("`{}` is not a valid tile", symbol)))
+                    },
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.into(),
+            width: width as u32,
+            height: rows.len() as u32,
+            tiles,
+            spawns,
+        })
+    }
+
+    /// Returns the number of tiles in a row.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the tile at given coordinates, `None` if they are outside the map.
+    pub fn tile(&self, x: u32, y: u32) -> Option<Tile> {
+        if x < self.width && y < self.height {
+            self.tiles.get((y * self.width + x) as usize).copied()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the coordinates of the spawn point of the team with given index, if any.
+    pub fn spawn(&self, team: u8) -> Option<(u32, u32)> {
+        self.spawns.get(&team).copied()
+    }
+
+    /// Returns an iterator over the coordinates of the tiles of given kind, row by row.
+    pub fn find(&self, tile: Tile) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width;
+
+        (0..)
+            .zip(&self.tiles)
+            .filter(move |(_, t)| **t == tile)
+            .map(move |(i, _)| (i % width, i / width))
+    }
+
+    /// Returns an iterator over the team indices and coordinates of the spawn points.
+    pub fn spawns(&self) -> impl Iterator<Item = (u8, (u32, u32))> + '_ {
+        self.spawns.iter().map(|(team, spawn)| (*team, *spawn))
+    }
+
+    /// Checks if there is a line of sight between two tiles.
+    ///
+    /// The line of sight is blocked if any tile on the straight line between the
+    /// tiles, excluding the tiles themselves, [blocks the sight](Tile::blocks_sight).
+    pub fn has_line_of_sight(&self, from: (u32, u32), to: (u32, u32)) -> bool {
+        line(from, to).into_iter().all(|(x, y)| !self.tile(x, y).is_some_and(|t| t.blocks_sight()))
+    }
+
+    /// Returns the text layout of the map.
+    pub fn to_ascii(&self) -> String {
+        self.rows().join("\n")
+    }
+
+    /// Returns the rows of the text layout of the map.
+    fn rows(&self) -> Vec<String> {
+        let mut rows: Vec<Vec<char>> = self
+            .tiles
+            .chunks(self.width as usize)
+            .map(|row| row.iter().map(Tile::symbol).collect())
+            .collect();
+
+        for (team, (x, y)) in self.spawns() {
+            if let Some(symbol) = char::from_digit(team.into(), 10) {
+                rows[y as usize][x as usize] = symbol;
+            }
+        }
+
+        rows.into_iter().map(String::from_iter).collect()
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_ascii())
+    }
+}
+
+/// The representation of a [`Map`] in JSON.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct MapLayout {
+    /// The name of the map.
+    name: String,
+    /// The lines of the text layout.
+    layout: Vec<String>,
+}
+
+impl TryFrom<MapLayout> for Map {
+    type Error = Error;
+
+    fn try_from(layout: MapLayout) -> Result<Self> {
+        Map::from_ascii(layout.name, &layout.layout.join("\n"))
+    }
+}
+
+impl From<Map> for MapLayout {
+    fn from(map: Map) -> Self {
+        Self { layout: map.rows(), name: map.name }
+    }
+}
+
+/// Returns the tiles on the straight line between two tiles, excluding the tiles
+/// themselves.
+///
+/// The line is drawn with Bresenham's line algorithm.
+fn line(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+    let (x1, y1) = (i64::from(to.0), i64::from(to.1));
+    let (mut x, mut y) = (i64::from(from.0), i64::from(from.1));
+
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut err = dx + dy;
+    let mut tiles = Vec::new();
+
+    while (x, y) != (x1, y1) {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+
+        if (x, y) != (x1, y1) {
+            tiles.push((x as u32, y as u32));
+        }
+    }
+
+    tiles
+}
+
+#[cfg(test)]
+mod test_map {
+    use super::*;
+
+    const LAYOUT: &str = "
+        ..1..
+        .*#*.
+        ~.M.~
+        .*#*.
+        ..0..
+    ";
+
+    #[test]
+    fn test_map() {
+        let map = Map::from_ascii("Hard Rock Mine", LAYOUT).unwrap();
+
+        assert_eq!((map.width(), map.height()), (5, 5));
+        assert_eq!(map.tile(2, 2), Some(Tile::GemMine));
+        assert_eq!(map.tile(5, 0), None);
+        assert_eq!(map.spawn(0), Some((2, 4)));
+
+        assert!(!map.has_line_of_sight((2, 4), (2, 0)));
+        assert!(map.has_line_of_sight((0, 4), (0, 0)));
+        assert!(map.has_line_of_sight((1, 3), (3, 1)));
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(map, serde_json::from_str(&json).unwrap());
+        assert!(Map::from_ascii("Broken", "..\n...").is_err());
+    }
+}
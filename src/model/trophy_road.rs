@@ -26,7 +26,7 @@ impl TrophyRoad {
     /// This only takes the trophy requirements into account and does not
     /// consider whether the player has collected the reward before or not.
     pub fn can_collect(&self, index: usize, trophies: u32) -> bool {
-        self.0.get(index).map_or(false, |r| r.can_collect(trophies))
+        self.0.get(index).is_some_and(|r| r.can_collect(trophies))
     }
 
     /// Returns an iterator of Trophy Road rewards that can be
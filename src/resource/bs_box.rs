@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
 use indexmap::IndexMap;
-use rand::prelude::{IteratorRandom, SliceRandom, ThreadRng};
+use rand::prelude::{IteratorRandom, SliceRandom};
+use rand::Rng;
 
 use super::power_points::PowerPoints;
 use crate::model::brawler::{Brawler, ChromaticSeason, Rarity};
@@ -99,9 +100,23 @@ impl BsBox {
     }
 
     /// Opens the [`BsBox`].
+    ///
+    /// Use [`open_with_rng`](Self::open_with_rng) to get reproducible rewards.
     pub fn open(&self, player_stats: PlayerStats) -> BoxRewards {
+        self.open_with_rng(player_stats, &mut rand::thread_rng())
+    }
+
+    /// Opens the [`BsBox`] using `rng` as the source of randomness.
+    ///
+    /// Opening a box with generators in the same state, such as two generators created
+    /// by [`rng::seeded`] from the same seed, gives the same rewards.
+    pub fn open_with_rng<R>(&self, player_stats: PlayerStats, rng: &mut R) -> BoxRewards
+    where
+        R: Rng + ?Sized,
+    {
         let box_data = self.box_type.box_data();
-        let mut gold = rng::weighted_random(box_data.gold[0], box_data.gold[1], box_data.gold[2]);
+        let [lower, upper, avg] = box_data.gold;
+        let mut gold = rng::weighted_random(lower, upper, avg, rng);
 
         let mut rarities = Vec::new();
         let mut gadgets = 0;
@@ -110,7 +125,7 @@ impl BsBox {
 
         let mut stacks = 0;
 
-        let selected = BoxItem::select_items(&player_stats.odds, box_data.total);
+        let selected = BoxItem::select_items(&player_stats.odds, box_data.total, rng);
 
         for item in selected {
             match item {
@@ -135,14 +150,14 @@ impl BsBox {
 
         let mut rewards = BoxRewards { gold, ..Default::default() };
 
-        add_power_points(stacks, &box_data, unlockable_data.power_points, &mut rewards);
+        add_power_points(stacks, &box_data, unlockable_data.power_points, &mut rewards, rng);
 
-        let mut missed = add_brawlers(rarities, &mut unlockable_data.brawlers, &mut rewards);
-        missed += add_gadgets(gadgets, &mut unlockable_data.gadgets, &mut rewards);
-        missed += add_star_powers(star_powers, &mut unlockable_data.star_powers, &mut rewards);
+        let mut missed = add_brawlers(rarities, &mut unlockable_data.brawlers, &mut rewards, rng);
+        missed += add_gadgets(gadgets, &mut unlockable_data.gadgets, &mut rewards, rng);
+        missed += add_star_powers(star_powers, &mut unlockable_data.star_powers, &mut rewards, rng);
         token_doubler_odds *= 2u32.pow(missed);
 
-        if token_doubler_odds >= (0..100).choose(&mut rand::thread_rng()).unwrap() {
+        if token_doubler_odds >= (0..100).choose(rng).unwrap() {
             rewards.add_token_doublers(TOKEN_DOUBLER_QUANTITY);
         }
 
@@ -222,7 +237,7 @@ impl<'a> PlayerStats<'a> {
     }
 
     /// Returns [`Unlockable`] data for the player.
-    fn get_unlockable_data(&self) -> Unlockable {
+    fn get_unlockable_data(&self) -> Unlockable<'_> {
         let mut brawlers = HashMap::new();
         for brawler in self.all_brawlers {
             let rarity = if let Some(rarity) = UnlockableRarity::from_rarity(brawler.rarity) {
@@ -336,9 +351,9 @@ impl TwoVariantsInfo {
     /// Returns [`TwoVariantsInfo`] after choosing one of the possible two variants.
     ///
     /// If at least one of the variants is false, it simply returns a copy of itself.
-    fn choose_one(&self, mut rng: &mut ThreadRng) -> Self {
+    fn choose_one<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
         if self.first && self.second {
-            let choice = *[1, 2].choose(&mut rng).unwrap();
+            let choice = *[1, 2].choose(rng).unwrap();
             Self { first: choice == 1, second: choice == 2 }
         } else {
             *self
@@ -443,9 +458,9 @@ enum BoxItem {
 
 impl BoxItem {
     /// Selects random `total` items with specified odds.
-    pub fn select_items(odds: &BoxOdds, total: u8) -> Vec<Self> {
+    pub fn select_items<R: Rng + ?Sized>(odds: &BoxOdds, total: u8, rng: &mut R) -> Vec<Self> {
         let mut items = Vec::new();
-        let choices = vec![
+        let choices = [
             (Self::PowerPoints, odds.power_points),
             (Self::Brawler(UnlockableRarity::Rare), odds.rare),
             (Self::Brawler(UnlockableRarity::SuperRare), odds.super_rare),
@@ -456,13 +471,9 @@ impl BoxItem {
             (Self::StarPower, odds.star_power),
         ];
 
-        let mut rng = rand::thread_rng();
-
         for _ in 0..total as usize {
-            let item = choices
-                .choose_weighted(&mut rng, |item| item.1)
-                .unwrap_or(&(Self::PowerPoints, 0.0))
-                .0;
+            let item =
+                choices.choose_weighted(rng, |item| item.1).unwrap_or(&(Self::PowerPoints, 0.0)).0;
             items.push(item);
         }
 
@@ -587,19 +598,20 @@ impl UnlockedStarPowers {
 }
 
 /// Adds reward [`PowerPoints`] to [`BoxRewards`].
-fn add_power_points(
+fn add_power_points<R: Rng + ?Sized>(
     stacks: usize,
     box_data: &BoxData,
     mut power_points_map: IndexMap<&str, u32>,
     rewards: &mut BoxRewards,
+    rng: &mut R,
 ) {
     if stacks > 0 {
         let [lower, upper, avg] = box_data.power_points;
-        let power_points = rng::weighted_random(lower, upper, avg);
-        let pieces = rng::split_in_integers(power_points, stacks as u32, 1);
+        let power_points = rng::weighted_random(lower, upper, avg, rng);
+        let pieces = rng::split_in_integers(power_points, stacks as u32, 1, rng);
 
         // let mut power_points_map = unlockable_data.power_points;
-        rng::shuffle_index_map(&mut power_points_map, &mut rand::thread_rng());
+        rng::shuffle_index_map(&mut power_points_map, rng);
 
         for piece in pieces {
             for (&brawler, &threshold) in &power_points_map {
@@ -619,10 +631,11 @@ fn add_power_points(
 /// not preserve the order of the Brawlers.
 ///
 /// Returns the number of rarities missed.
-fn add_brawlers(
+fn add_brawlers<R: Rng + ?Sized>(
     rarities: Vec<UnlockableRarity>,
     unlockable_brawlers: &mut HashMap<UnlockableRarity, Vec<&str>>,
     rewards: &mut BoxRewards,
+    rng: &mut R,
 ) -> u32 {
     let mut missed = 0;
     for rarity in rarities {
@@ -630,7 +643,7 @@ fn add_brawlers(
             // Unwrapping here is fine here because `get_valid_rarity` ensures the rarity
             // is present in the map and the rarity has at least one unlockable Brawler.
             let brawlers = unlockable_brawlers.get_mut(&rarity).unwrap();
-            let index = (0..brawlers.len()).choose(&mut rand::thread_rng()).unwrap();
+            let index = (0..brawlers.len()).choose(rng).unwrap();
             rewards.add_brawler(brawlers[index]);
             brawlers.swap_remove(index);
         } else {
@@ -647,14 +660,15 @@ fn add_brawlers(
 /// not preserve the order of the Brawlers.
 ///
 /// Returns the number of Gadgets that could not be added.
-fn add_gadgets(
+fn add_gadgets<R: Rng + ?Sized>(
     total: u32,
     unlockable_gadgets: &mut HashMap<&str, TwoVariantsInfo>,
     rewards: &mut BoxRewards,
+    rng: &mut R,
 ) -> u32 {
     let mut missed = 0;
     for _ in 0..total {
-        if let Some((brawler, choice)) = handle_two_variants(unlockable_gadgets) {
+        if let Some((brawler, choice)) = handle_two_variants(unlockable_gadgets, rng) {
             rewards.add_gadgets(brawler, UnlockedGadgets(choice));
         } else {
             missed += 1;
@@ -670,14 +684,15 @@ fn add_gadgets(
 /// not preserve the order of the Brawlers.
 ///
 /// Returns the number of Star Powers that could not be added.
-fn add_star_powers(
+fn add_star_powers<R: Rng + ?Sized>(
     total: u32,
     unlockable_star_powers: &mut HashMap<&str, TwoVariantsInfo>,
     rewards: &mut BoxRewards,
+    rng: &mut R,
 ) -> u32 {
     let mut missed = 0;
     for _ in 0..total {
-        if let Some((brawler, choice)) = handle_two_variants(unlockable_star_powers) {
+        if let Some((brawler, choice)) = handle_two_variants(unlockable_star_powers, rng) {
             rewards.add_star_powers(brawler, UnlockedStarPowers(choice));
         } else {
             missed += 1;
@@ -693,13 +708,19 @@ fn add_star_powers(
 ///
 /// Returns the selected Brawler and the variant. `None` is returned when the mapping
 /// is empty.
-fn handle_two_variants<'a>(
+fn handle_two_variants<'a, R: Rng + ?Sized>(
     mapping: &mut HashMap<&'a str, TwoVariantsInfo>,
+    rng: &mut R,
 ) -> Option<(&'a str, TwoVariantsInfo)> {
-    let mut rng = rand::thread_rng();
+    // The Brawlers are sorted because the iteration order of a `HashMap` is random,
+    // which would make the choice irreproducible even with a seeded `rng`.
+    let mut brawlers: Vec<&str> = mapping.keys().copied().collect();
+    brawlers.sort_unstable();
 
-    if let Some((&brawler, variants_info)) = mapping.iter().choose(&mut rng) {
-        let choice = variants_info.choose_one(&mut rng);
+    if let Some((brawler, variants_info)) =
+        brawlers.choose(rng).and_then(|&b| mapping.get(b).map(|info| (b, info)))
+    {
+        let choice = variants_info.choose_one(rng);
 
         // Remove the unlocked variant from the available variants for the Brawler.
         if let Some(entry) = mapping.get_mut(&brawler) {
@@ -733,7 +754,7 @@ fn get_valid_rarity(
     let mut opt_rarity = Some(rarity);
     loop {
         if let Some(rarity) = opt_rarity {
-            if unlockable_brawlers.get(&rarity).map_or(false, |v| !v.is_empty()) {
+            if unlockable_brawlers.get(&rarity).is_some_and(|v| !v.is_empty()) {
                 return opt_rarity;
             } else {
                 opt_rarity = rarity.lower();
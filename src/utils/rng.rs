@@ -4,15 +4,31 @@
 
 use indexmap::IndexMap;
 use rand::distributions::{Distribution, Uniform, WeightedIndex};
+use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+
+/// The random number generator used during games.
+///
+/// It is seeded from a known value so that games can be reproduced.
+pub type GameRng = StdRng;
+
+/// Creates a new [`GameRng`] from `seed`.
+///
+/// Two generators created from the same seed produce the same numbers.
+pub fn seeded(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
 
 /// Generates a weighted random number between `lower` and `upper`.
 ///
 /// The average of the randomly generated numbers is close to `avg`.
 ///
 /// Panics if `lower > avg` or `avg > upper`.
-pub fn weighted_random(lower: u32, upper: u32, avg: u32) -> u32 {
+pub fn weighted_random<R>(lower: u32, upper: u32, avg: u32, rng: &mut R) -> u32
+where
+    R: Rng + ?Sized,
+{
     assert!(lower <= avg && avg <= upper);
 
     let avg_low = (lower + avg) / 2;
@@ -20,10 +36,9 @@ pub fn weighted_random(lower: u32, upper: u32, avg: u32) -> u32 {
 
     let p_high = (avg - avg_low) / (avg_high - avg_low);
 
-    let mut rng = rand::thread_rng();
     let (low, high) = if rng.gen::<u32>() < p_high { (avg, upper) } else { (lower, avg) };
 
-    Uniform::new_inclusive(low, high).sample(&mut rng)
+    Uniform::new_inclusive(low, high).sample(rng)
 }
 
 /// Randomly splits an integer into `total` integers that add up to it.
@@ -31,7 +46,10 @@ pub fn weighted_random(lower: u32, upper: u32, avg: u32) -> u32 {
 /// All the numbers in the returned vector are more than or equal to `minimum`.
 ///
 /// Returns an empty vector if `total * minimum > number` or `total = 0`.
-pub fn split_in_integers(number: u32, total: u32, minimum: u32) -> Vec<u32> {
+pub fn split_in_integers<R>(number: u32, total: u32, minimum: u32, rng: &mut R) -> Vec<u32>
+where
+    R: Rng + ?Sized,
+{
     if total * minimum > number || total == 0 {
         return Vec::new();
     } else if number == 0 {
@@ -39,7 +57,7 @@ pub fn split_in_integers(number: u32, total: u32, minimum: u32) -> Vec<u32> {
     }
 
     let max = number - (total * minimum) + total - 1;
-    let mut breaks = (0..max).choose_multiple(&mut rand::thread_rng(), total as usize - 1);
+    let mut breaks = (0..max).choose_multiple(rng, total as usize - 1);
     breaks.sort_unstable();
     breaks.push(max);
 
@@ -56,8 +74,11 @@ pub fn split_in_integers(number: u32, total: u32, minimum: u32) -> Vec<u32> {
 ///
 /// Returns `None` if the length of `options` is not equal to the length of `weights`,
 /// `weights` is empty or the sum of all `weights` is 0.
-pub fn select_one<'a, T>(options: &'a [T], weights: &[u32]) -> Option<&'a T> {
-    WeightedIndex::new(weights).ok().and_then(|w| options.get(w.sample(&mut rand::thread_rng())))
+pub fn select_one<'a, T, R>(options: &'a [T], weights: &[u32], rng: &mut R) -> Option<&'a T>
+where
+    R: Rng + ?Sized,
+{
+    WeightedIndex::new(weights).ok().and_then(|w| options.get(w.sample(rng)))
 }
 
 /// Sample a number uniformly between 0 and `ubound`. Uses 32-bit sampling where
@@ -70,7 +91,7 @@ pub fn select_one<'a, T>(options: &'a [T], weights: &[u32]) -> Option<&'a T> {
 /// [`rand`]: https://docs.rs/rand/0.8.3/rand/
 #[inline]
 fn gen_index<R: Rng + ?Sized>(rng: &mut R, ubound: usize) -> usize {
-    if ubound <= (core::u32::MAX as usize) {
+    if ubound <= (u32::MAX as usize) {
         rng.gen_range(0..ubound as u32) as usize
     } else {
         rng.gen_range(0..ubound)
@@ -92,3 +113,22 @@ where
         map.swap_indices(i, gen_index(rng, i + 1));
     }
 }
+
+#[cfg(test)]
+mod test_rng {
+    use super::*;
+
+    #[test]
+    fn test_seeded() {
+        let (mut first, mut second) = (seeded(42), seeded(42));
+
+        assert_eq!(
+            split_in_integers(100, 5, 1, &mut first),
+            split_in_integers(100, 5, 1, &mut second)
+        );
+        assert_eq!(
+            weighted_random(0, 100, 20, &mut first),
+            weighted_random(0, 100, 20, &mut second)
+        );
+    }
+}